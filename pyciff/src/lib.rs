@@ -1,15 +1,22 @@
 use std::path::PathBuf;
 
+use ciff::{CiffToPisa, JsonlToCiff, PayloadSlice};
+use memmap::Mmap;
 use pyo3::exceptions::PyRuntimeError;
 use pyo3::prelude::*;
+use pyo3::types::PyBytes;
 use pyo3::wrap_pyfunction;
 
+fn to_py_err(err: impl std::fmt::Display) -> PyErr {
+    PyRuntimeError::new_err(err.to_string())
+}
+
 /// Converts a CIFF index stored in `input_file` to a PISA "binary collection"
 /// (uncompressed inverted index) with a basename `output`.
 #[pyfunction]
 fn ciff_to_pisa_internal(input_file: &str, output: &str) -> PyResult<()> {
-    ciff::ciff_to_pisa(&PathBuf::from(input_file), &PathBuf::from(output))
-        .map_err(|err| PyRuntimeError::new_err(err.to_string()))
+    ciff::ciff_to_pisa(&PathBuf::from(input_file), &PathBuf::from(output), true)
+        .map_err(to_py_err)
 }
 
 #[pyfunction]
@@ -28,7 +35,218 @@ pub fn pisa_to_ciff_internal(
         &PathBuf::from(output),
         description,
     )
-    .map_err(|err| PyRuntimeError::new_err(err.to_string()))
+    .map_err(to_py_err)
+}
+
+/// Builds a term or title lexicon from the newline-delimited strings in `input`, in the order
+/// they appear (they are not sorted), writing it in [`ciff::PayloadVector`] format to
+/// `output`. Open it back with
+/// [`PyPayloadSlice`].
+#[pyfunction]
+fn build_lexicon(input: &str, output: &str) -> PyResult<()> {
+    ciff::build_lexicon(&PathBuf::from(input), &PathBuf::from(output)).map_err(to_py_err)
+}
+
+/// Builder for converting a CIFF file to a PISA "binary collection", mirroring
+/// [`ciff::CiffToPisa`].
+#[pyclass]
+#[derive(Default)]
+struct PyCiffToPisa {
+    input_path: Option<PathBuf>,
+    output_base_path: Option<PathBuf>,
+    skip_lexicons: bool,
+}
+
+#[pymethods]
+impl PyCiffToPisa {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the CIFF path. Required.
+    fn input_path(mut slf: PyRefMut<'_, Self>, path: &str) -> PyRefMut<'_, Self> {
+        slf.input_path = Some(PathBuf::from(path));
+        slf
+    }
+
+    /// Sets the output PISA index basename. Required.
+    fn output_paths(mut slf: PyRefMut<'_, Self>, base_path: &str) -> PyRefMut<'_, Self> {
+        slf.output_base_path = Some(PathBuf::from(base_path));
+        slf
+    }
+
+    /// Do not construct document and term lexicons.
+    fn skip_lexicons(mut slf: PyRefMut<'_, Self>) -> PyRefMut<'_, Self> {
+        slf.skip_lexicons = true;
+        slf
+    }
+
+    /// Runs the conversion. If `on_progress` is not `None`, it is called as
+    /// `on_progress(postings_seen, total_postings)` after each posting list is converted.
+    fn convert(&self, py: Python<'_>, on_progress: Option<PyObject>) -> PyResult<()> {
+        let input_path = self
+            .input_path
+            .as_ref()
+            .ok_or_else(|| PyRuntimeError::new_err("input_path was not set"))?;
+        let output_base_path = self
+            .output_base_path
+            .as_ref()
+            .ok_or_else(|| PyRuntimeError::new_err("output_paths was not set"))?;
+
+        let mut converter = CiffToPisa::default();
+        converter
+            .input_path(input_path.clone())
+            .output_paths(output_base_path.clone());
+        if self.skip_lexicons {
+            converter.skip_lexicons();
+        }
+        if let Some(callback) = on_progress {
+            converter.on_progress(move |done, total| {
+                Python::with_gil(|py| {
+                    if let Err(err) = callback.call1(py, (done, total)) {
+                        err.print(py);
+                    }
+                });
+            });
+        }
+        py.allow_threads(|| converter.convert()).map_err(to_py_err)
+    }
+}
+
+/// Builder for converting a JSONL document collection to a CIFF file, mirroring
+/// [`ciff::JsonlToCiff`].
+#[pyclass]
+#[derive(Default)]
+struct PyJsonlToCiff {
+    input_path: Option<PathBuf>,
+    output_path: Option<PathBuf>,
+    quantize: bool,
+}
+
+#[pymethods]
+impl PyJsonlToCiff {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the path of the JSONL file. Required.
+    fn input_path(mut slf: PyRefMut<'_, Self>, path: &str) -> PyRefMut<'_, Self> {
+        slf.input_path = Some(PathBuf::from(path));
+        slf
+    }
+
+    /// Set the output CIFF file path. Required.
+    fn output_path(mut slf: PyRefMut<'_, Self>, path: &str) -> PyRefMut<'_, Self> {
+        slf.output_path = Some(PathBuf::from(path));
+        slf
+    }
+
+    /// Set whether to quantize scores to integers. See [`ciff::JsonlToCiff::quantize`].
+    fn quantize(mut slf: PyRefMut<'_, Self>, quantize: bool) -> PyRefMut<'_, Self> {
+        slf.quantize = quantize;
+        slf
+    }
+
+    /// Runs the conversion.
+    fn convert(&self, py: Python<'_>) -> PyResult<()> {
+        let input_path = self
+            .input_path
+            .as_ref()
+            .ok_or_else(|| PyRuntimeError::new_err("input_path was not set"))?;
+        let output_path = self
+            .output_path
+            .as_ref()
+            .ok_or_else(|| PyRuntimeError::new_err("output_path was not set"))?;
+
+        let mut converter = JsonlToCiff::default();
+        converter
+            .input_path(input_path.clone())
+            .output_path(output_path.clone());
+        converter.quantize(self.quantize);
+        py.allow_threads(|| converter.convert()).map_err(to_py_err)
+    }
+}
+
+/// Read-only, memmap-backed view over a lexicon file written by [`build_lexicon`] (or
+/// [`ciff::PayloadVector::write`]).
+#[pyclass]
+struct PyPayloadSlice {
+    // Only read through `slice`, which views it as a `PayloadSlice` for the duration of each
+    // call.
+    mmap: Mmap,
+}
+
+impl PyPayloadSlice {
+    fn slice(&self) -> &PayloadSlice {
+        PayloadSlice::new(&self.mmap)
+    }
+}
+
+#[pymethods]
+impl PyPayloadSlice {
+    #[new]
+    fn new(path: &str) -> PyResult<Self> {
+        let file = std::fs::File::open(path).map_err(to_py_err)?;
+        let mmap = unsafe { Mmap::map(&file) }.map_err(to_py_err)?;
+        Ok(Self { mmap })
+    }
+
+    fn __len__(&self) -> usize {
+        self.slice().len() as usize
+    }
+
+    fn __getitem__<'py>(&self, py: Python<'py>, index: u64) -> PyResult<&'py PyBytes> {
+        self.slice()
+            .get(index)
+            .map(|bytes| PyBytes::new(py, bytes))
+            .ok_or_else(|| PyRuntimeError::new_err(format!("index {index} out of bounds")))
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyResult<Py<PyPayloadSliceIter>> {
+        Py::new(
+            slf.py(),
+            PyPayloadSliceIter {
+                parent: slf.into(),
+                index: 0,
+            },
+        )
+    }
+
+    /// Binary-searches for `term`, returning its index or `None` if absent. `self` must already
+    /// be sorted byte-lexicographically, as [`build_lexicon`]'s input is required to be.
+    fn position(&self, term: &[u8]) -> Option<u64> {
+        self.slice().position(term)
+    }
+}
+
+/// Iterator over a [`PyPayloadSlice`], yielding each element as `bytes`.
+#[pyclass]
+struct PyPayloadSliceIter {
+    parent: Py<PyPayloadSlice>,
+    index: u64,
+}
+
+#[pymethods]
+impl PyPayloadSliceIter {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__<'py>(mut slf: PyRefMut<'py, Self>, py: Python<'py>) -> Option<&'py PyBytes> {
+        let index = slf.index;
+        let parent = slf.parent.borrow(py);
+        let item = parent
+            .slice()
+            .get(index)
+            .map(|bytes| PyBytes::new(py, bytes));
+        drop(parent);
+        if item.is_some() {
+            slf.index += 1;
+        }
+        item
+    }
 }
 
 /// A Python module implemented in Rust.
@@ -36,5 +254,9 @@ pub fn pisa_to_ciff_internal(
 fn pyciff(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(ciff_to_pisa_internal, m)?)?;
     m.add_function(wrap_pyfunction!(pisa_to_ciff_internal, m)?)?;
+    m.add_function(wrap_pyfunction!(build_lexicon, m)?)?;
+    m.add_class::<PyCiffToPisa>()?;
+    m.add_class::<PyJsonlToCiff>()?;
+    m.add_class::<PyPayloadSlice>()?;
     Ok(())
 }
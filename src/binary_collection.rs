@@ -1,10 +1,264 @@
+use bytes::{Buf, BufMut};
 use std::convert::TryFrom;
 use std::convert::TryInto;
 use std::error::Error;
 use std::fmt;
+use std::io::{self, Read, Write};
 
 const ELEMENT_SIZE: usize = std::mem::size_of::<u32>();
 
+/// Encodes a sequence of ascending `u32` values into `writer` as gaps between consecutive
+/// elements (the first value is stored as-is), each gap VByte-encoded: split into 7-bit
+/// groups, low groups first, with the high bit of every byte set to `1` except the final
+/// byte, which has it set to `0` to mark the end of that value.
+///
+/// Unlike [`encode_u32_sequence`], the resulting per-sequence byte length is variable, so
+/// collections built from this function must be read back with [`VByteCollection`] /
+/// [`VByteSequence`] rather than [`BinaryCollection`]; callers should track which format a
+/// collection uses externally (e.g. with a format marker in a surrounding header), since
+/// nothing in the byte stream itself distinguishes the two.
+///
+/// # Errors
+///
+/// Passes along any IO errors.
+///
+/// # Examples
+///
+/// ```
+/// # use ciff::{encode_vbyte_sequence, VByteCollection};
+/// # use std::convert::TryFrom;
+/// # fn main() -> anyhow::Result<()> {
+/// let mut buf: Vec<u8> = vec![];
+/// encode_vbyte_sequence(&mut buf, 3, &[4_u32, 98765, 98766])?;
+///
+/// let mut collection = VByteCollection::try_from(&buf[..])?;
+/// let sequence = collection.next().unwrap()?;
+/// assert_eq!(sequence.iter().collect::<Vec<_>>(), vec![4_u32, 98765, 98766]);
+/// # Ok(())
+/// # }
+/// ```
+pub fn encode_vbyte_sequence<N, S, W>(writer: &mut W, len: u32, sequence: S) -> io::Result<()>
+where
+    N: std::borrow::Borrow<u32>,
+    S: IntoIterator<Item = N>,
+    W: Write,
+{
+    writer.write_all(&len.to_le_bytes())?;
+    let mut prev = 0_u32;
+    for (i, value) in sequence.into_iter().enumerate() {
+        let value = *value.borrow();
+        let gap = if i == 0 { value } else { value - prev };
+        write_vbyte(writer, gap)?;
+        prev = value;
+    }
+    Ok(())
+}
+
+fn write_vbyte<W: Write>(writer: &mut W, mut value: u32) -> io::Result<()> {
+    loop {
+        let low = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            writer.write_all(&[low])?;
+            return Ok(());
+        }
+        writer.write_all(&[low | 0x80])?;
+    }
+}
+
+/// Decodes a single VByte-encoded value from the front of `bytes`, returning the decoded
+/// value together with the number of bytes it occupied.
+fn read_vbyte(bytes: &[u8]) -> Result<(u32, usize), InvalidFormat> {
+    let mut value: u32 = 0;
+    let mut shift = 0;
+    for (i, &byte) in bytes.iter().enumerate() {
+        value |= u32::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok((value, i + 1));
+        }
+        shift += 7;
+    }
+    Err(InvalidFormat::new("Truncated VByte sequence"))
+}
+
+/// Encodes a sequence of 4-byte unsigned integers into `buf` in little-endian order, mirroring
+/// [`crate::encode_u32_sequence`] but writing through the [`bytes::BufMut`] abstraction instead
+/// of a plain [`std::io::Write`]r.
+///
+/// # Examples
+///
+/// ```
+/// # use ciff::encode_u32_sequence_buf;
+/// let mut buf: Vec<u8> = vec![];
+/// encode_u32_sequence_buf(&mut buf, 2, &[4_u32, 98765]);
+/// assert_eq!(buf, &[2, 0, 0, 0, 4, 0, 0, 0, 205, 129, 1, 0]);
+/// ```
+pub fn encode_u32_sequence_buf<B: BufMut>(buf: &mut B, len: u32, values: &[u32]) {
+    buf.put_u32_le(len);
+    for &value in values {
+        buf.put_u32_le(value);
+    }
+}
+
+/// Iterates over a [`BinaryCollection`]-formatted stream held in any [`bytes::Buf`], such as
+/// a `Bytes` assembled from several non-contiguous chunks, without first concatenating it
+/// into a single contiguous slice.
+pub struct BufBinaryCollection<B> {
+    buf: B,
+}
+
+impl<B: Buf> BufBinaryCollection<B> {
+    /// Wraps `buf` in a [`bytes::Buf`]-driven binary collection iterator.
+    pub fn new(buf: B) -> Self {
+        Self { buf }
+    }
+}
+
+impl<B: Buf> Iterator for BufBinaryCollection<B> {
+    type Item = Result<Vec<u32>, InvalidFormat>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.buf.has_remaining() {
+            return None;
+        }
+        Some(BinarySequence::from_buf(&mut self.buf))
+    }
+}
+
+impl<'a> BinarySequence<'a> {
+    /// Decodes a single length-prefixed sequence from the front of `buf`, advancing its
+    /// cursor past the consumed bytes. Because a [`bytes::Buf`] is not generally backed by a
+    /// single contiguous slice (e.g. a chain of non-adjacent chunks), the decoded values are
+    /// returned owned rather than borrowed, unlike [`BinarySequence`] itself.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InvalidFormat`] if `buf` does not hold a full length prefix and body.
+    pub fn from_buf<B: Buf>(buf: &mut B) -> Result<Vec<u32>, InvalidFormat> {
+        if buf.remaining() < ELEMENT_SIZE {
+            return Err(InvalidFormat::new(
+                "Not enough bytes remaining to read a sequence length",
+            ));
+        }
+        let length = buf.get_u32_le() as usize;
+        if buf.remaining() < ELEMENT_SIZE * length {
+            return Err(InvalidFormat::new(
+                "Not enough bytes remaining to read a sequence body",
+            ));
+        }
+        Ok((0..length).map(|_| buf.get_u32_le()).collect())
+    }
+}
+
+/// A [`BinaryCollection`]-like container whose sequences are VByte-compressed, as produced
+/// by [`encode_vbyte_sequence`]. Each sequence still starts with a 4-byte element count, but
+/// the payload that follows has a variable byte length, so sequences must be decoded in
+/// order rather than sliced at a fixed stride.
+#[derive(Debug, Clone, Copy)]
+pub struct VByteCollection<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> TryFrom<&'a [u8]> for VByteCollection<'a> {
+    type Error = InvalidFormat;
+    fn try_from(bytes: &'a [u8]) -> Result<Self, Self::Error> {
+        Ok(Self { bytes })
+    }
+}
+
+fn vbyte_get_from(bytes: &[u8]) -> Result<(VByteSequence<'_>, usize), InvalidFormat> {
+    let length_bytes = bytes
+        .get(..ELEMENT_SIZE)
+        .ok_or_else(InvalidFormat::default)?;
+    let length = u32::from_le_bytes(length_bytes.try_into().unwrap()) as usize;
+    let mut offset = ELEMENT_SIZE;
+    for _ in 0..length {
+        let (_, consumed) = read_vbyte(bytes.get(offset..).ok_or_else(InvalidFormat::default)?)?;
+        offset += consumed;
+    }
+    Ok((
+        VByteSequence {
+            bytes: &bytes[ELEMENT_SIZE..offset],
+            length,
+        },
+        offset,
+    ))
+}
+
+impl<'a> Iterator for VByteCollection<'a> {
+    type Item = Result<VByteSequence<'a>, InvalidFormat>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.bytes.is_empty() {
+            return None;
+        }
+        match vbyte_get_from(self.bytes) {
+            Ok((sequence, consumed)) => {
+                self.bytes = &self.bytes[consumed..];
+                Some(Ok(sequence))
+            },
+            Err(err) => {
+                self.bytes = &[];
+                Some(Err(err))
+            },
+        }
+    }
+}
+
+/// A single VByte-compressed sequence of ascending `u32` values, as yielded by
+/// [`VByteCollection`].
+#[derive(Debug, Clone, Copy)]
+pub struct VByteSequence<'a> {
+    bytes: &'a [u8],
+    length: usize,
+}
+
+impl<'a> VByteSequence<'a> {
+    /// Returns the number of elements in the sequence.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    /// Checks if the sequence is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Returns an iterator decoding the gaps back into ascending absolute values.
+    #[must_use]
+    pub fn iter(&self) -> VByteSequenceIterator<'a> {
+        VByteSequenceIterator {
+            bytes: self.bytes,
+            remaining: self.length,
+            prev: 0,
+        }
+    }
+}
+
+/// Iterator over the decoded elements of a [`VByteSequence`].
+pub struct VByteSequenceIterator<'a> {
+    bytes: &'a [u8],
+    remaining: usize,
+    prev: u32,
+}
+
+impl<'a> Iterator for VByteSequenceIterator<'a> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let (gap, consumed) = read_vbyte(self.bytes).expect("sequence was already validated");
+        self.bytes = &self.bytes[consumed..];
+        self.prev += gap;
+        self.remaining -= 1;
+        Some(self.prev)
+    }
+}
+
 /// Error raised when the bytes cannot be properly parsed into the collection format.
 #[derive(Debug, Default, PartialEq, Eq)]
 pub struct InvalidFormat(Option<String>);
@@ -221,6 +475,18 @@ impl<'a> RandomAccessBinaryCollection<'a> {
         }
     }
 
+    /// Returns a [`DocsetCursor`] over the sequence at the given index, or `None` if out of
+    /// bounds.
+    ///
+    /// Combined with random access to the sequences themselves, this lets a postings-list
+    /// intersection gallop between terms: advance the cursor with the smaller current docid
+    /// via [`DocsetCursor::skip_to`] the other cursor's docid, alternating, instead of
+    /// stepping through both lists element by element.
+    #[must_use]
+    pub fn cursor(&self, index: usize) -> Option<DocsetCursor<'a>> {
+        self.get(index).map(|sequence| sequence.cursor())
+    }
+
     /// Returns the sequence at the given index or `None` if out of bounds.
     #[must_use]
     pub fn get(&self, index: usize) -> Option<BinarySequence<'a>> {
@@ -247,6 +513,74 @@ impl<'a> RandomAccessBinaryCollection<'a> {
     pub fn is_empty(&self) -> bool {
         self.offsets.len() == 0
     }
+
+    /// Serializes the offset table to `writer` as a length-prefixed sequence of
+    /// little-endian `u64`s, so it can be persisted as a sidecar file next to the data
+    /// and later used to reconstruct the collection without rescanning it. See
+    /// [`RandomAccessBinaryCollection::from_offsets`].
+    ///
+    /// # Errors
+    ///
+    /// Passes along any IO errors.
+    pub fn write_offsets<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&(self.offsets.len() as u64).to_le_bytes())?;
+        for &offset in &self.offsets {
+            writer.write_all(&(offset as u64).to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Reconstructs a collection from `bytes` and a previously [`write_offsets`]-ten offset
+    /// table, skipping the linear scan that [`TryFrom::try_from`] otherwise performs.
+    ///
+    /// [`write_offsets`]: RandomAccessBinaryCollection::write_offsets
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`InvalidFormat`] error if the last offset does not point at a valid
+    /// sequence, if the last offset plus its sequence length does not equal the length of
+    /// `bytes`, or if the table is empty while `bytes` is not. Offsets before the last one are
+    /// not checked.
+    pub fn from_offsets(bytes: &'a [u8], offsets: Vec<usize>) -> Result<Self, InvalidFormat> {
+        let inner = BinaryCollection::try_from(bytes)?;
+        if let Some(&last_offset) = offsets.last() {
+            let last_sequence = get_from(
+                inner
+                    .bytes
+                    .get(last_offset..)
+                    .ok_or_else(|| InvalidFormat::new("Offset table does not fit the data"))?,
+            )?;
+            let end = last_offset + ELEMENT_SIZE * (last_sequence.len() + 1);
+            if end != inner.bytes.len() {
+                return Err(InvalidFormat::new(
+                    "Last offset and sequence length do not cover the whole collection",
+                ));
+            }
+        } else if !inner.bytes.is_empty() {
+            return Err(InvalidFormat::new(
+                "Offset table is empty but the collection is not",
+            ));
+        }
+        Ok(Self { inner, offsets })
+    }
+
+    /// Reads an offset table previously written by [`write_offsets`](Self::write_offsets).
+    ///
+    /// # Errors
+    ///
+    /// Passes along any IO errors, including an unexpected end of stream.
+    pub fn read_offsets<R: Read>(mut reader: R) -> io::Result<Vec<usize>> {
+        let mut len_bytes = [0_u8; 8];
+        reader.read_exact(&mut len_bytes)?;
+        let len = u64::from_le_bytes(len_bytes) as usize;
+        let mut offsets = Vec::with_capacity(len);
+        for _ in 0..len {
+            let mut offset_bytes = [0_u8; 8];
+            reader.read_exact(&mut offset_bytes)?;
+            offsets.push(u64::from_le_bytes(offset_bytes) as usize);
+        }
+        Ok(offsets)
+    }
 }
 
 /// A single binary sequence.
@@ -359,6 +693,14 @@ impl<'a> BinarySequence<'a> {
     pub fn bytes(&'a self) -> &'a [u8] {
         self.bytes
     }
+
+    /// Returns a [`DocSet`]-style cursor over this sequence, positioned at its first element.
+    ///
+    /// [`DocSet`]: DocsetCursor
+    #[must_use]
+    pub fn cursor(&self) -> DocsetCursor<'a> {
+        DocsetCursor::new(*self)
+    }
 }
 
 pub struct BinarySequenceIterator<'a> {
@@ -376,6 +718,241 @@ impl<'a> Iterator for BinarySequenceIterator<'a> {
     }
 }
 
+/// Outcome of [`DocsetCursor::skip_to`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipOutcome {
+    /// The cursor landed exactly on the requested docid.
+    Reached(u32),
+    /// The requested docid is not present; the cursor landed on the smallest docid greater
+    /// than it.
+    Overstep(u32),
+    /// Every remaining docid was smaller than the requested one; the cursor is now past the
+    /// end of the sequence.
+    End,
+}
+
+/// A `DocSet`-style cursor over a [`BinarySequence`] of ascending docids, supporting
+/// skip-to-or-past (`skip_to`) in addition to plain forward iteration.
+///
+/// Because [`BinarySequence::get`] is `O(1)` random access into a memory-mapped, monotonically
+/// increasing slice, `skip_to` binary-searches the remainder of the sequence rather than
+/// scanning forward element by element, giving genuine sub-linear skip performance.
+///
+/// # Examples
+///
+/// ```
+/// # use ciff::{BinarySequence, SkipOutcome};
+/// # use std::convert::TryFrom;
+/// let bytes: [u8; 20] = [1, 0, 0, 0, 4, 0, 0, 0, 9, 0, 0, 0, 16, 0, 0, 0, 25, 0, 0, 0];
+/// let sequence = BinarySequence::try_from(&bytes[..]).unwrap();
+/// let mut cursor = sequence.cursor();
+/// assert_eq!(cursor.docid(), Some(1));
+/// assert_eq!(cursor.skip_to(10), SkipOutcome::Overstep(16));
+/// assert_eq!(cursor.docid(), Some(16));
+/// assert_eq!(cursor.next(), Some(25));
+/// assert_eq!(cursor.next(), None);
+/// assert!(cursor.at_end());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct DocsetCursor<'a> {
+    sequence: BinarySequence<'a>,
+    position: usize,
+}
+
+impl<'a> DocsetCursor<'a> {
+    /// Creates a cursor positioned at the first element of `sequence`.
+    #[must_use]
+    pub fn new(sequence: BinarySequence<'a>) -> Self {
+        Self {
+            sequence,
+            position: 0,
+        }
+    }
+
+    /// Returns the docid at the cursor, or `None` once it has advanced past the end.
+    #[must_use]
+    pub fn docid(&self) -> Option<u32> {
+        self.sequence.get(self.position)
+    }
+
+    /// Returns `true` once the cursor has advanced past the last element.
+    #[must_use]
+    pub fn at_end(&self) -> bool {
+        self.position >= self.sequence.len()
+    }
+
+    /// Advances the cursor by one position and returns the docid there, or `None` if it is
+    /// now past the end.
+    pub fn next(&mut self) -> Option<u32> {
+        self.position += 1;
+        self.docid()
+    }
+
+    /// Advances the cursor to the first docid greater than or equal to `target`, without
+    /// moving backwards, and reports whether that docid was an exact match, an overstep, or
+    /// whether the sequence was exhausted first.
+    ///
+    /// Binary-searches the `[position, len)` remainder of the underlying sequence, since its
+    /// docids are monotonically increasing and `BinarySequence::get` is `O(1)`.
+    pub fn skip_to(&mut self, target: u32) -> SkipOutcome {
+        let mut lo = self.position;
+        let mut hi = self.sequence.len();
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let docid = self
+                .sequence
+                .get(mid)
+                .expect("mid is within [0, sequence.len())");
+            if docid < target {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        self.position = lo;
+        match self.docid() {
+            Some(docid) if docid == target => SkipOutcome::Reached(docid),
+            Some(docid) => SkipOutcome::Overstep(docid),
+            None => SkipOutcome::End,
+        }
+    }
+}
+
+/// Error raised while pulling a single sequence out of a [`BinaryCollectionReader`].
+#[derive(Debug)]
+pub enum ReadSequenceError {
+    /// An I/O error occurred while reading from the underlying source.
+    Io(io::Error),
+    /// The bytes read so far do not form a valid sequence, e.g., the body was
+    /// shorter than the length it was prefixed with.
+    Format(InvalidFormat),
+    /// The source was exhausted exactly on a sequence boundary: there was nothing left to read.
+    Eof,
+}
+
+impl fmt::Display for ReadSequenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "I/O error: {err}"),
+            Self::Format(err) => write!(f, "{err}"),
+            Self::Eof => write!(f, "end of stream"),
+        }
+    }
+}
+
+impl Error for ReadSequenceError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Format(err) => Some(err),
+            Self::Eof => None,
+        }
+    }
+}
+
+impl From<io::Error> for ReadSequenceError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// Lazily decodes a [`BinaryCollection`]-formatted stream from any [`Read`] source, one
+/// length-prefixed sequence at a time, without ever materializing the whole collection.
+///
+/// Unlike [`BinaryCollection`], which requires the entire buffer up front, this reads just
+/// enough bytes off `R` to yield the next sequence, reusing an internal buffer across calls.
+/// This makes it suitable for large PISA collections streamed from a file, socket, or
+/// decompressor.
+///
+/// # Examples
+///
+/// ```
+/// # use ciff::{encode_u32_sequence, BinaryCollectionReader};
+/// # fn main() -> anyhow::Result<()> {
+/// let mut buffer: Vec<u8> = Vec::new();
+/// encode_u32_sequence(&mut buffer, 3, &[1, 2, 3])?;
+/// encode_u32_sequence(&mut buffer, 1, &[4])?;
+///
+/// let mut reader = BinaryCollectionReader::new(&buffer[..]);
+/// assert_eq!(reader.read_sequence()?, Some(vec![1_u32, 2, 3]));
+/// assert_eq!(reader.read_sequence()?, Some(vec![4_u32]));
+/// assert_eq!(reader.read_sequence()?, None);
+/// # Ok(())
+/// # }
+/// ```
+pub struct BinaryCollectionReader<R> {
+    input: R,
+    buffer: Vec<u8>,
+}
+
+impl<R: Read> BinaryCollectionReader<R> {
+    /// Wraps `input` in a streaming binary collection reader.
+    pub fn new(input: R) -> Self {
+        Self {
+            input,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Reads and returns the next sequence, or `None` if the stream ended exactly on a
+    /// sequence boundary.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ReadSequenceError::Format`] if the length prefix was read but the body
+    /// was truncated, and [`ReadSequenceError::Io`] on any other I/O failure.
+    pub fn read_sequence(&mut self) -> Result<Option<Vec<u32>>, ReadSequenceError> {
+        match self.next_sequence() {
+            Ok(sequence) => Ok(Some(sequence)),
+            Err(ReadSequenceError::Eof) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn next_sequence(&mut self) -> Result<Vec<u32>, ReadSequenceError> {
+        let mut length_bytes = [0_u8; ELEMENT_SIZE];
+        let bytes_read = read_up_to(&mut self.input, &mut length_bytes)?;
+        if bytes_read == 0 {
+            return Err(ReadSequenceError::Eof);
+        }
+        if bytes_read < ELEMENT_SIZE {
+            return Err(ReadSequenceError::Format(InvalidFormat::new(
+                "Unexpected end of stream while reading a sequence length",
+            )));
+        }
+        let length = u32::from_le_bytes(length_bytes) as usize;
+
+        self.buffer.resize(ELEMENT_SIZE * length, 0);
+        let bytes_read = read_up_to(&mut self.input, &mut self.buffer)?;
+        if bytes_read < self.buffer.len() {
+            return Err(ReadSequenceError::Format(InvalidFormat::new(
+                "Unexpected end of stream while reading a sequence body",
+            )));
+        }
+
+        Ok(self
+            .buffer
+            .chunks_exact(ELEMENT_SIZE)
+            .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect())
+    }
+}
+
+/// Fills `buf` as much as possible, stopping early only at the end of the stream, and
+/// returns the number of bytes actually read (may be less than `buf.len()`).
+fn read_up_to<R: Read>(input: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut total = 0;
+    while total < buf.len() {
+        match input.read(&mut buf[total..]) {
+            Ok(0) => break,
+            Ok(n) => total += n,
+            Err(ref err) if err.kind() == io::ErrorKind::Interrupted => {},
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(total)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -507,4 +1084,196 @@ mod test {
         let coll = RandomAccessBinaryCollection::try_from(COLLECTION_BYTES.as_ref()).unwrap();
         let _ = coll.at(10);
     }
+
+    #[test]
+    fn test_random_access_binary_collection_cursor_galloping_intersection() {
+        let coll = RandomAccessBinaryCollection::try_from(COLLECTION_BYTES.as_ref()).unwrap();
+        let mut t5 = coll.cursor(6).unwrap(); // [0, 1, 2]
+        let mut t6 = coll.cursor(7).unwrap(); // [1, 2]
+
+        // Intersect by alternately skipping the trailing cursor to the leading docid.
+        let mut intersection = Vec::new();
+        loop {
+            match (t5.docid(), t6.docid()) {
+                (Some(a), Some(b)) if a == b => {
+                    intersection.push(a);
+                    if t5.next().is_none() || t6.next().is_none() {
+                        break;
+                    }
+                }
+                (Some(a), Some(b)) if a < b => {
+                    if t5.skip_to(b) == SkipOutcome::End {
+                        break;
+                    }
+                }
+                (Some(_), Some(_)) => {
+                    if t6.skip_to(t5.docid().unwrap()) == SkipOutcome::End {
+                        break;
+                    }
+                }
+                _ => break,
+            }
+        }
+        assert_eq!(intersection, vec![1, 2]);
+
+        assert!(coll.cursor(coll.len()).is_none());
+    }
+
+    #[test]
+    fn test_binary_collection_reader() {
+        let mut reader = BinaryCollectionReader::new(COLLECTION_BYTES.as_ref());
+        let mut sequences = Vec::new();
+        while let Some(sequence) = reader.read_sequence().unwrap() {
+            sequences.push(sequence);
+        }
+        assert_eq!(
+            sequences,
+            vec![
+                vec![3_u32],
+                vec![0],
+                vec![0],
+                vec![0],
+                vec![0],
+                vec![2],
+                vec![0, 1, 2],
+                vec![1, 2],
+                vec![0, 1, 2],
+                vec![1],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_binary_collection_reader_truncated() {
+        let bytes: Vec<u8> = vec![3, 0, 0, 0, 1, 0, 0, 0];
+        let mut reader = BinaryCollectionReader::new(bytes.as_slice());
+        assert!(matches!(
+            reader.read_sequence(),
+            Err(ReadSequenceError::Format(_))
+        ));
+    }
+
+    #[test]
+    fn test_offset_sidecar_round_trip() {
+        let coll = RandomAccessBinaryCollection::try_from(COLLECTION_BYTES.as_ref()).unwrap();
+        let mut sidecar = Vec::new();
+        coll.write_offsets(&mut sidecar).unwrap();
+
+        let offsets = RandomAccessBinaryCollection::read_offsets(sidecar.as_slice()).unwrap();
+        assert_eq!(offsets, coll.offsets);
+
+        let restored =
+            RandomAccessBinaryCollection::from_offsets(COLLECTION_BYTES.as_ref(), offsets)
+                .unwrap();
+        assert_eq!(restored.len(), coll.len());
+        for idx in 0..coll.len() {
+            assert_eq!(
+                restored.at(idx).iter().collect::<Vec<u32>>(),
+                coll.at(idx).iter().collect::<Vec<u32>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_offset_sidecar_mismatched_length() {
+        let coll = RandomAccessBinaryCollection::try_from(COLLECTION_BYTES.as_ref()).unwrap();
+        let mut offsets = coll.offsets.clone();
+        offsets.pop();
+        assert!(RandomAccessBinaryCollection::from_offsets(COLLECTION_BYTES.as_ref(), offsets)
+            .is_err());
+    }
+
+    #[test]
+    fn test_binary_collection_reader_empty() {
+        let mut reader = BinaryCollectionReader::new(&[][..]);
+        assert!(reader.read_sequence().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_vbyte_round_trip() {
+        let sequences = vec![vec![1_u32, 2, 3], vec![4_u32], vec![5_u32, 6, 1_000_000]];
+        let mut buffer = Vec::new();
+        for sequence in &sequences {
+            encode_vbyte_sequence(&mut buffer, sequence.len() as u32, sequence).unwrap();
+        }
+
+        let decoded: Vec<Vec<u32>> = VByteCollection::try_from(buffer.as_slice())
+            .unwrap()
+            .map(|sequence| sequence.map(|s| s.iter().collect::<Vec<_>>()))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(decoded, sequences);
+    }
+
+    #[test]
+    fn test_buf_u32_sequence_round_trip() {
+        let mut buf = Vec::new();
+        encode_u32_sequence_buf(&mut buf, 3, &[1_u32, 2, 3]);
+        encode_u32_sequence_buf(&mut buf, 1, &[4_u32]);
+
+        let sequences: Vec<Vec<u32>> = BufBinaryCollection::new(buf.as_slice())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(sequences, vec![vec![1_u32, 2, 3], vec![4_u32]]);
+    }
+
+    #[test]
+    fn test_buf_from_buf_truncated() {
+        let mut buf = Vec::new();
+        encode_u32_sequence_buf(&mut buf, 3, &[1_u32, 2, 3]);
+        buf.truncate(buf.len() - 1);
+        let mut slice = buf.as_slice();
+        assert!(BinarySequence::from_buf(&mut slice).is_err());
+    }
+
+    #[test]
+    fn test_docset_cursor_skip_to() {
+        let coll = BinaryCollection::try_from(COLLECTION_BYTES.as_ref()).unwrap();
+        let sequence = coll.skip(6).next().unwrap().unwrap(); // t5: [0, 1, 2]
+        let mut cursor = sequence.cursor();
+        assert_eq!(cursor.docid(), Some(0));
+        assert_eq!(cursor.skip_to(1), SkipOutcome::Reached(1));
+        assert_eq!(cursor.docid(), Some(1));
+        assert_eq!(
+            cursor.skip_to(1),
+            SkipOutcome::Reached(1),
+            "skip_to must not move backwards"
+        );
+        assert_eq!(cursor.skip_to(10), SkipOutcome::End);
+        assert!(cursor.at_end());
+        assert_eq!(cursor.next(), None);
+    }
+
+    #[test]
+    fn test_docset_cursor_skip_to_overstep() {
+        let coll = BinaryCollection::try_from(COLLECTION_BYTES.as_ref()).unwrap();
+        let sequence = coll.skip(7).next().unwrap().unwrap(); // t6: [1, 2]
+        let mut cursor = sequence.cursor();
+        assert_eq!(
+            cursor.skip_to(0),
+            SkipOutcome::Overstep(1),
+            "skip_to a docid not present lands on the next greater one"
+        );
+        assert_eq!(cursor.skip_to(2), SkipOutcome::Reached(2));
+    }
+
+    #[test]
+    fn test_docset_cursor_next() {
+        let coll = BinaryCollection::try_from(COLLECTION_BYTES.as_ref()).unwrap();
+        let sequence = coll.skip(7).next().unwrap().unwrap(); // t6: [1, 2]
+        let mut cursor = sequence.cursor();
+        assert_eq!(cursor.docid(), Some(1));
+        assert_eq!(cursor.next(), Some(2));
+        assert_eq!(cursor.next(), None);
+        assert!(cursor.at_end());
+    }
+
+    #[test]
+    fn test_vbyte_truncated() {
+        let mut buffer = Vec::new();
+        encode_vbyte_sequence(&mut buffer, 2_u32, &[1_u32, 300]).unwrap();
+        buffer.truncate(buffer.len() - 1);
+        let mut collection = VByteCollection::try_from(buffer.as_slice()).unwrap();
+        assert!(collection.next().unwrap().is_err());
+    }
 }
@@ -0,0 +1,228 @@
+//! A single `ciff` binary that exposes every conversion in this crate as a subcommand,
+//! instead of shipping each one as its own executable.
+//! Refer to [`osirrc/ciff`](https://github.com/osirrc/ciff) on Github
+//! for more detailed information about the format.
+
+#![warn(
+    missing_docs,
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_import_braces,
+    unused_qualifications
+)]
+#![warn(clippy::all, clippy::pedantic)]
+#![allow(clippy::module_name_repetitions, clippy::default_trait_access)]
+
+use anyhow::Result;
+use ciff::{
+    CiffToJsonl, CiffToPisa, CiffValidator, DocumentFormat, JsonlToCiff, ParquetToCiff,
+    PisaToCiff, QuantizationScheme,
+};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "ciff", about = "Convert to and from the Common Index Format [v1]")]
+enum Args {
+    /// Generates a PISA index from a CIFF file.
+    ToPisa {
+        #[structopt(short, long, help = "Path to ciff export file")]
+        ciff_file: PathBuf,
+        #[structopt(short, long, help = "Output basename")]
+        output: PathBuf,
+        #[structopt(short, long, help = "Generate lexicon files?")]
+        generate_lexicons: bool,
+        #[structopt(
+            long,
+            help = "Compute quantized BM25 impact scores into a parallel .scores file"
+        )]
+        quantize_impacts: bool,
+        #[structopt(
+            long,
+            help = "Bit width of quantized BM25 impact scores",
+            default_value = "8"
+        )]
+        quantize_bits: u8,
+        #[structopt(long, help = "BM25 k1 parameter", default_value = "0.9")]
+        bm25_k1: f32,
+        #[structopt(long, help = "BM25 b parameter", default_value = "0.4")]
+        bm25_b: f32,
+        #[structopt(
+            long,
+            help = "Reorder documents via recursive graph bisection to improve compression"
+        )]
+        recursive_graph_bisection: bool,
+    },
+    /// Converts a PISA index to a CIFF file.
+    FromPisa {
+        #[structopt(short, long, help = "Binary collection (uncompressed) basename")]
+        collection: PathBuf,
+        #[structopt(short, long, help = "Path to terms text file")]
+        terms: PathBuf,
+        #[structopt(short, long, help = "Path to documents text file")]
+        documents: PathBuf,
+        #[structopt(short, long, help = "Output filename")]
+        output: PathBuf,
+        #[structopt(long, help = "Index description")]
+        description: Option<String>,
+        #[structopt(
+            long,
+            help = "Read quantized BM25 impact scores from .scores instead of .freqs"
+        )]
+        quantize_impacts: bool,
+    },
+    /// Converts a CIFF file into a jsonl file.
+    ToJsonl {
+        #[structopt(short, long, help = "Path to CIFF file")]
+        input: PathBuf,
+        #[structopt(short, long, help = "Output jsonl file")]
+        output: PathBuf,
+    },
+    /// Converts a jsonl file into a CIFF file.
+    FromJsonl {
+        #[structopt(short, long, help = "Path to jsonl file")]
+        input: PathBuf,
+        #[structopt(short, long, help = "Output CIFF file")]
+        output: PathBuf,
+        #[structopt(short, long, help = "Quantize scores to integers")]
+        quantize: bool,
+        #[structopt(long, help = "Bit width of quantized scores", default_value = "8")]
+        quantize_bits: u8,
+        #[structopt(
+            long,
+            help = "Quantization scheme: linear or log",
+            default_value = "linear"
+        )]
+        quantization_scheme: QuantizationScheme,
+        #[structopt(
+            long,
+            help = "Input document format: ndjson, json-array, csv, or auto",
+            default_value = "auto"
+        )]
+        format: DocumentFormat,
+        #[structopt(
+            long,
+            help = "Number of threads to use, or 0 to let rayon pick",
+            default_value = "0"
+        )]
+        threads: usize,
+    },
+    /// Converts a columnar Parquet file of sparse document vectors into a CIFF file.
+    FromParquet {
+        #[structopt(short, long, help = "Path to Parquet file")]
+        input: PathBuf,
+        #[structopt(short, long, help = "Output CIFF file")]
+        output: PathBuf,
+        #[structopt(short, long, help = "Quantize scores to integers")]
+        quantize: bool,
+        #[structopt(long, help = "Bit width of quantized scores", default_value = "8")]
+        quantize_bits: u8,
+        #[structopt(
+            long,
+            help = "Quantization scheme: linear or log",
+            default_value = "linear"
+        )]
+        quantization_scheme: QuantizationScheme,
+        #[structopt(
+            long,
+            help = "Number of threads to use, or 0 to let rayon pick",
+            default_value = "0"
+        )]
+        threads: usize,
+    },
+    /// Checks a CIFF file for internal consistency without converting it.
+    Validate {
+        #[structopt(short, long, help = "Path to CIFF file")]
+        input: PathBuf,
+    },
+}
+
+fn run(args: Args) -> Result<()> {
+    match args {
+        Args::ToPisa {
+            ciff_file,
+            output,
+            generate_lexicons,
+            quantize_impacts,
+            quantize_bits,
+            bm25_k1,
+            bm25_b,
+            recursive_graph_bisection,
+        } => {
+            let mut converter = CiffToPisa::default();
+            converter.input_path(ciff_file).output_paths(output);
+            if !generate_lexicons {
+                converter.skip_lexicons();
+            }
+            if quantize_impacts {
+                converter.with_quantized_scores(quantize_bits, bm25_k1, bm25_b);
+            }
+            converter.recursive_graph_bisection(recursive_graph_bisection);
+            converter.convert()
+        }
+        Args::FromPisa {
+            collection,
+            terms,
+            documents,
+            output,
+            description,
+            quantize_impacts,
+        } => PisaToCiff::default()
+            .description(description.unwrap_or_default())
+            .index_paths(collection)
+            .terms_path(terms)
+            .titles_path(documents)
+            .output_path(output)
+            .quantize_impacts(quantize_impacts)
+            .convert(),
+        Args::ToJsonl { input, output } => CiffToJsonl::default()
+            .input_path(input)
+            .output_path(output)
+            .convert(),
+        Args::FromJsonl {
+            input,
+            output,
+            quantize,
+            quantize_bits,
+            quantization_scheme,
+            format,
+            threads,
+        } => JsonlToCiff::default()
+            .input_path(input)
+            .output_path(output)
+            .quantize(quantize)
+            .quantize_bits(quantize_bits)
+            .quantization_scheme(quantization_scheme)
+            .format(format)
+            .threads(threads)
+            .convert(),
+        Args::FromParquet {
+            input,
+            output,
+            quantize,
+            quantize_bits,
+            quantization_scheme,
+            threads,
+        } => ParquetToCiff::default()
+            .input_path(input)
+            .output_path(output)
+            .quantize(quantize)
+            .quantize_bits(quantize_bits)
+            .quantization_scheme(quantization_scheme)
+            .threads(threads)
+            .convert(),
+        Args::Validate { input } => {
+            let report = CiffValidator::default().input_path(input).validate()?;
+            print!("{report}");
+            anyhow::ensure!(report.is_valid(), "CIFF file failed validation");
+            Ok(())
+        }
+    }
+}
+
+fn main() {
+    if let Err(error) = run(Args::from_args()) {
+        eprintln!("ERROR: {error}");
+        std::process::exit(1);
+    }
+}
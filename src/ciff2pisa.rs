@@ -28,6 +28,26 @@ struct Args {
     output: PathBuf,
     #[structopt(short, long, help = "Generate lexicon files?")]
     generate_lexicons: bool,
+    #[structopt(
+        long,
+        help = "Compute quantized BM25 impact scores into a parallel .scores file"
+    )]
+    quantize_impacts: bool,
+    #[structopt(
+        long,
+        help = "Bit width of quantized BM25 impact scores",
+        default_value = "8"
+    )]
+    quantize_bits: u8,
+    #[structopt(long, help = "BM25 k1 parameter", default_value = "0.9")]
+    bm25_k1: f32,
+    #[structopt(long, help = "BM25 b parameter", default_value = "0.4")]
+    bm25_b: f32,
+    #[structopt(
+        long,
+        help = "Reorder documents via recursive graph bisection to improve compression"
+    )]
+    recursive_graph_bisection: bool,
 }
 
 fn main() {
@@ -39,6 +59,10 @@ fn main() {
     if !args.generate_lexicons {
         converter.skip_lexicons();
     }
+    if args.quantize_impacts {
+        converter.with_quantized_scores(args.quantize_bits, args.bm25_k1, args.bm25_b);
+    }
+    converter.recursive_graph_bisection(args.recursive_graph_bisection);
     if let Err(error) = converter.convert() {
         eprintln!("ERROR: {}", error);
         std::process::exit(1);
@@ -1,7 +1,7 @@
 mod proto;
 pub use proto::{DocRecord, Header, Posting, PostingsList};
 
-use ciff::JsonlToCiff;
+use ciff::{DocumentFormat, JsonlToCiff, QuantizationScheme};
 use std::path::PathBuf;
 use structopt::StructOpt;
 
@@ -17,6 +17,26 @@ struct Args {
     output: PathBuf,
     #[structopt(short, long, help = "Quantize scores to integers")]
     quantize: bool,
+    #[structopt(long, help = "Bit width of quantized scores", default_value = "8")]
+    quantize_bits: u8,
+    #[structopt(
+        long,
+        help = "Quantization scheme: linear or log",
+        default_value = "linear"
+    )]
+    quantization_scheme: QuantizationScheme,
+    #[structopt(
+        long,
+        help = "Input document format: ndjson, json-array, csv, or auto",
+        default_value = "auto"
+    )]
+    format: DocumentFormat,
+    #[structopt(
+        long,
+        help = "Number of threads to use, or 0 to let rayon pick",
+        default_value = "0"
+    )]
+    threads: usize,
 }
 
 fn main() {
@@ -26,7 +46,11 @@ fn main() {
     converter
         .input_path(args.input)
         .output_path(args.output)
-        .quantize(args.quantize);
+        .quantize(args.quantize)
+        .quantize_bits(args.quantize_bits)
+        .quantization_scheme(args.quantization_scheme)
+        .format(args.format)
+        .threads(args.threads);
 
     if let Err(error) = converter.convert() {
         eprintln!("ERROR: {error}");
@@ -52,20 +52,27 @@
 )]
 
 use anyhow::{anyhow, Context};
+use arrow::array::{Array, Float64Array, Int64Array, ListArray, MapArray, StringArray};
+use arrow::datatypes::{DataType, Schema};
+use arrow::record_batch::RecordBatch;
 use indicatif::ProgressIterator;
 use indicatif::{ProgressBar, ProgressStyle};
 use memmap::Mmap;
 use num_traits::ToPrimitive;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
 use protobuf::{CodedInputStream, CodedOutputStream};
-use serde::Deserialize;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
 use std::convert::TryFrom;
 use std::ffi::{OsStr, OsString};
 use std::fmt;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tempfile::TempDir;
 
 mod proto;
@@ -73,11 +80,19 @@ pub use proto::{DocRecord, Posting, PostingsList};
 
 mod binary_collection;
 pub use binary_collection::{
-    BinaryCollection, BinarySequence, InvalidFormat, RandomAccessBinaryCollection,
+    encode_u32_sequence_buf, encode_vbyte_sequence, BinaryCollection, BinaryCollectionReader,
+    BinarySequence, BufBinaryCollection, DocsetCursor, InvalidFormat, RandomAccessBinaryCollection,
+    ReadSequenceError, SkipOutcome, VByteCollection, VByteSequence,
 };
 
 mod payload_vector;
-pub use payload_vector::{build_lexicon, PayloadIter, PayloadSlice, PayloadVector};
+pub use payload_vector::{
+    build_front_coded_lexicon, build_lexicon, CompressedPayloadSlice, CompressedPayloadVector,
+    FrontCodedLexicon, FrontCodedLexiconSlice, OffsetWidth, PayloadIter, PayloadSlice,
+    PayloadVector,
+};
+
+mod recursive_graph_bisection;
 
 type Result<T> = anyhow::Result<T>;
 
@@ -86,8 +101,10 @@ const DEFAULT_PROGRESS_TEMPLATE: &str =
 
 /// Minimum value for quantized scores.
 const MIN_QUANTIZED_VALUE: i32 = 1;
-/// Maximum value for quantized scores.
-const MAX_QUANTIZED_VALUE: i32 = 255;
+
+/// Default in-memory budget for buffered `(term, docid, tf)` triples in
+/// [`JsonlToCiff::max_memory`] before they are spilled to a run file on disk.
+const DEFAULT_MAX_MEMORY_BYTES: usize = 256 * 1024 * 1024;
 
 /// Wraps [`proto::Header`] and additionally provides some important counts that are already cast
 /// to an unsigned type.
@@ -126,6 +143,75 @@ impl fmt::Display for Header {
     }
 }
 
+/// Lazily reads `count` messages of type `M` directly off a [`CodedInputStream`], one
+/// `read_message` call at a time, so a caller never has to hold more than one decoded
+/// message resident at once.
+struct MessageReader<'a, 'i, M> {
+    input: &'a mut CodedInputStream<'i>,
+    remaining: u32,
+    _message: std::marker::PhantomData<M>,
+}
+
+impl<'a, 'i, M: protobuf::Message> Iterator for MessageReader<'a, 'i, M> {
+    type Item = Result<M>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        Some(self.input.read_message::<M>().map_err(Into::into))
+    }
+}
+
+/// Streams a CIFF file's records directly off a [`CodedInputStream`]: the header, then the
+/// posting lists, then the document records, each pulled one length-delimited protobuf
+/// message at a time rather than all at once.
+///
+/// Because the underlying format has no random-access index, the two iterators must be
+/// consumed in the order they appear on disk: all posting lists before any document record.
+/// Both borrow `self` mutably, which statically prevents interleaving them out of order.
+struct CiffRecords<'a, 'i> {
+    input: &'a mut CodedInputStream<'i>,
+    header: Header,
+}
+
+impl<'a, 'i> CiffRecords<'a, 'i> {
+    /// Reads the header off `input` and prepares to stream the records that follow it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header cannot be parsed, or contains negative counts.
+    fn new(input: &'a mut CodedInputStream<'i>) -> Result<Self> {
+        let header = Header::from_stream(input)?;
+        Ok(Self { input, header })
+    }
+
+    /// Returns the previously parsed header.
+    fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Lazily reads the posting lists, one at a time. Must be fully drained before
+    /// [`CiffRecords::doc_records`] is called.
+    fn postings_lists(&mut self) -> MessageReader<'_, 'i, PostingsList> {
+        MessageReader {
+            input: self.input,
+            remaining: self.header.num_postings_lists,
+            _message: std::marker::PhantomData,
+        }
+    }
+
+    /// Lazily reads the document records, one at a time.
+    fn doc_records(&mut self) -> MessageReader<'_, 'i, DocRecord> {
+        MessageReader {
+            input: self.input,
+            remaining: self.header.num_documents,
+            _message: std::marker::PhantomData,
+        }
+    }
+}
+
 /// Returns default progress style.
 fn pb_style() -> ProgressStyle {
     ProgressStyle::default_bar()
@@ -172,11 +258,84 @@ where
     Ok(())
 }
 
+/// BM25 term-weighting parameters used to compute the impact scores in
+/// [`CiffToPisa::quantize_impacts`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bm25Params {
+    /// Term frequency saturation parameter.
+    pub k1: f32,
+    /// Document length normalization parameter.
+    pub b: f32,
+}
+
+impl Default for Bm25Params {
+    fn default() -> Self {
+        Self { k1: 0.9, b: 0.4 }
+    }
+}
+
+/// Computes the (unquantized) BM25 impact of a posting with frequency `tf` in a document of
+/// length `doc_length`, for a term with document frequency `df`, within a collection of
+/// `num_docs` documents with average document length `avg_doc_length`.
+fn bm25_impact(
+    tf: i64,
+    df: i64,
+    doc_length: f64,
+    num_docs: u32,
+    avg_doc_length: f64,
+    params: Bm25Params,
+) -> f64 {
+    let tf = tf as f64;
+    let k1 = f64::from(params.k1);
+    let b = f64::from(params.b);
+    let idf = (1.0 + (f64::from(num_docs) - df as f64 + 0.5) / (df as f64 + 0.5)).ln();
+    let length_norm = 1.0 - b + b * (doc_length / avg_doc_length);
+    idf * (tf * (k1 + 1.0)) / (tf + k1 * length_norm)
+}
+
+/// Looks up the length of `docid` in `doc_lengths`, falling back to `avg_doc_length` if the
+/// posting references a docid without a corresponding document record (e.g. a CIFF file whose
+/// statistics reflect a larger collection than the chunk of document records it carries).
+fn doc_length_or_avg(doc_lengths: &[i32], avg_doc_length: f64, docid: i32) -> f64 {
+    usize::try_from(docid)
+        .ok()
+        .and_then(|docid| doc_lengths.get(docid))
+        .map_or(avg_doc_length, |&length| f64::from(length))
+}
+
+/// Linearly maps `impact` from `[0, max_impact]` onto `[MIN_QUANTIZED_VALUE, 2.pow(bits) - 1]`,
+/// for use as the scalar quantization scheme in [`CiffToPisa::with_quantized_scores`].
+fn quantize_impact(impact: f64, max_impact: f64, bits: u8) -> u32 {
+    let max_quantized = (1_i32 << bits) - 1;
+    if max_impact <= 0.0 {
+        return MIN_QUANTIZED_VALUE as u32;
+    }
+    let quantization_range = max_quantized - MIN_QUANTIZED_VALUE;
+    let quantized = ((impact / max_impact) * f64::from(quantization_range)
+        + f64::from(MIN_QUANTIZED_VALUE))
+    .round() as i32;
+    quantized.clamp(MIN_QUANTIZED_VALUE, max_quantized) as u32
+}
+
+/// Per-collection statistics and parameters needed to compute BM25 impacts while streaming
+/// posting lists in [`convert_to_pisa`].
+struct ImpactContext {
+    /// Document lengths, indexed by docid.
+    doc_lengths: Vec<i32>,
+    num_docs: u32,
+    avg_doc_length: f64,
+    max_impact: f64,
+    /// Bit width to quantize impacts into.
+    bits: u8,
+    params: Bm25Params,
+}
+
 fn write_posting_list<DW, FW, TW>(
     posting_list: &PostingsList,
     documents: &mut DW,
     frequencies: &mut FW,
     terms: &mut TW,
+    scores: Option<(&mut dyn Write, &ImpactContext)>,
 ) -> Result<()>
 where
     DW: Write,
@@ -207,6 +366,27 @@ where
             .map(|p| u32::try_from(p.get_tf()).expect("Negative frequency")),
     )?;
 
+    if let Some((scores, ctx)) = scores {
+        let df = posting_list.get_df();
+        encode_u32_sequence(
+            scores,
+            length,
+            postings.iter().scan(0_i32, |docid, p| {
+                *docid += p.get_docid();
+                let doc_length = doc_length_or_avg(&ctx.doc_lengths, ctx.avg_doc_length, *docid);
+                let impact = bm25_impact(
+                    i64::from(p.get_tf()),
+                    df,
+                    doc_length,
+                    ctx.num_docs,
+                    ctx.avg_doc_length,
+                    ctx.params,
+                );
+                Some(quantize_impact(impact, ctx.max_impact, ctx.bits))
+            }),
+        )?;
+    }
+
     writeln!(terms, "{}", posting_list.get_term())?;
     Ok(())
 }
@@ -243,6 +423,9 @@ struct PisaIndexPaths {
     documents: PathBuf,
     frequencies: PathBuf,
     sizes: PathBuf,
+    /// Quantized BM25 impact scores, parallel to `frequencies`. The file is only written by
+    /// [`CiffToPisa::quantize_impacts`] and only read by [`PisaToCiff::quantize_impacts`].
+    scores: PathBuf,
 }
 
 impl PisaIndexPaths {
@@ -252,6 +435,7 @@ impl PisaIndexPaths {
             documents: PathBuf::from(concat(path.as_ref(), ".docs")),
             frequencies: PathBuf::from(concat(path.as_ref(), ".freqs")),
             sizes: PathBuf::from(concat(path.as_ref(), ".sizes")),
+            scores: PathBuf::from(concat(path.as_ref(), ".scores")),
         }
     }
 }
@@ -305,6 +489,9 @@ fn reorder_pisa_index(paths: &PisaPaths) -> Result<()> {
     order.sort_by_key(|&i| &terms[i]);
     reorder_postings(&paths.index.documents, &order, true)?;
     reorder_postings(&paths.index.frequencies, &order, false)?;
+    if paths.index.scores.exists() {
+        reorder_postings(&paths.index.scores, &order, false)?;
+    }
     let mut term_writer = BufWriter::new(File::create(&paths.terms)?);
     for index in order {
         writeln!(&mut term_writer, "{}", terms[index])?;
@@ -312,8 +499,136 @@ fn reorder_pisa_index(paths: &PisaPaths) -> Result<()> {
     Ok(())
 }
 
+/// Reorders the documents of a PISA index using [`recursive_graph_bisection::compute_order`],
+/// remapping every posting's docid (and re-sorting each posting list by its new docid) in
+/// `.docs`/`.freqs`/`.scores`, then permuting `.sizes` and the titles file to match.
+fn reorder_documents_bp(paths: &PisaPaths) -> Result<()> {
+    let docs_temp = TempDir::new()?;
+    let docs_tmp_path = docs_temp.path().join("docs");
+    std::fs::rename(&paths.index.documents, &docs_tmp_path)?;
+    let docs_mmap = unsafe { Mmap::map(&File::open(&docs_tmp_path)?)? };
+    let docs_coll = RandomAccessBinaryCollection::try_from(docs_mmap.as_ref())?;
+
+    let num_docs = docs_coll
+        .at(0)
+        .get(0)
+        .ok_or_else(|| anyhow!("Missing document count"))? as usize;
+    let postings: Vec<Vec<u32>> = (1..docs_coll.len())
+        .map(|i| docs_coll.at(i).iter().collect())
+        .collect();
+    let order = recursive_graph_bisection::compute_order(&postings, num_docs);
+    let mut old_to_new = vec![0_u32; num_docs];
+    for (new_id, &old_id) in order.iter().enumerate() {
+        old_to_new[old_id] = new_id.to_u32().ok_or_else(|| anyhow!("Too many documents"))?;
+    }
+
+    let freqs_temp = TempDir::new()?;
+    let freqs_tmp_path = freqs_temp.path().join("freqs");
+    std::fs::rename(&paths.index.frequencies, &freqs_tmp_path)?;
+    let freqs_mmap = unsafe { Mmap::map(&File::open(&freqs_tmp_path)?)? };
+    let freqs_coll = RandomAccessBinaryCollection::try_from(freqs_mmap.as_ref())?;
+
+    let scores_data: Option<(TempDir, Mmap)> = if paths.index.scores.exists() {
+        let temp = TempDir::new()?;
+        let tmp_path = temp.path().join("scores");
+        std::fs::rename(&paths.index.scores, &tmp_path)?;
+        let mmap = unsafe { Mmap::map(&File::open(&tmp_path)?)? };
+        Some((temp, mmap))
+    } else {
+        None
+    };
+    let scores_coll = scores_data
+        .as_ref()
+        .map(|(_, mmap)| RandomAccessBinaryCollection::try_from(mmap.as_ref()))
+        .transpose()?;
+
+    let mut documents_writer = BufWriter::new(File::create(&paths.index.documents)?);
+    let mut frequencies_writer = BufWriter::new(File::create(&paths.index.frequencies)?);
+    let mut scores_writer = scores_coll
+        .is_some()
+        .then(|| File::create(&paths.index.scores).map(BufWriter::new))
+        .transpose()?;
+
+    encode_u32_sequence(&mut documents_writer, 1, docs_coll.at(0).iter())?;
+
+    for i in 1..docs_coll.len() {
+        let docids = docs_coll.at(i);
+        let freqs = freqs_coll.at(i);
+        let scores = scores_coll.as_ref().map(|coll| coll.at(i));
+        let mut postings: Vec<(u32, u32, u32)> = docids
+            .iter()
+            .zip(freqs.iter())
+            .enumerate()
+            .map(|(j, (docid, freq))| {
+                let score = scores.as_ref().and_then(|s| s.get(j)).unwrap_or_default();
+                (old_to_new[docid as usize], freq, score)
+            })
+            .collect();
+        postings.sort_unstable_by_key(|&(docid, _, _)| docid);
+
+        let length = postings
+            .len()
+            .to_u32()
+            .ok_or_else(|| anyhow!("Posting list too long"))?;
+        encode_u32_sequence(&mut documents_writer, length, postings.iter().map(|&(d, _, _)| d))?;
+        encode_u32_sequence(&mut frequencies_writer, length, postings.iter().map(|&(_, f, _)| f))?;
+        if let Some(writer) = scores_writer.as_mut() {
+            encode_u32_sequence(writer, length, postings.iter().map(|&(_, _, s)| s))?;
+        }
+    }
+    documents_writer.flush()?;
+    frequencies_writer.flush()?;
+    if let Some(writer) = scores_writer.as_mut() {
+        writer.flush()?;
+    }
+
+    reorder_sizes(&paths.index.sizes, &order)?;
+    reorder_titles(&paths.titles, &order)?;
+
+    Ok(())
+}
+
+/// Permutes the lengths in `.sizes` so that the document at new id `i` holds the length
+/// previously held by `order[i]`.
+fn reorder_sizes(path: &Path, order: &[usize]) -> Result<()> {
+    let temp = TempDir::new()?;
+    let tmp_path = temp.path().join("sizes");
+    std::fs::rename(path, &tmp_path)?;
+    let mmap = unsafe { Mmap::map(&File::open(tmp_path)?)? };
+    let coll = RandomAccessBinaryCollection::try_from(mmap.as_ref())?;
+    let sizes = coll.at(0);
+    let mut writer = BufWriter::new(File::create(path)?);
+    let length = order
+        .len()
+        .to_u32()
+        .ok_or_else(|| anyhow!("Too many documents"))?;
+    encode_u32_sequence(
+        &mut writer,
+        length,
+        order
+            .iter()
+            .map(|&old_id| sizes.get(old_id).expect("docid is in range")),
+    )?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Permutes the titles file so that the document at new id `i` holds the title previously held
+/// by `order[i]`.
+fn reorder_titles(path: &Path, order: &[usize]) -> Result<()> {
+    let titles = BufReader::new(File::open(path)?)
+        .lines()
+        .collect::<io::Result<Vec<_>>>()?;
+    let mut writer = BufWriter::new(File::create(path)?);
+    for &old_id in order {
+        writeln!(&mut writer, "{}", titles[old_id])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
 /// CIFF to PISA converter.
-#[derive(Debug, Default, Clone)]
+#[derive(Clone)]
 pub struct CiffToPisa {
     input: Option<PathBuf>,
     documents_path: Option<PathBuf>,
@@ -323,6 +638,54 @@ pub struct CiffToPisa {
     titles_path: Option<PathBuf>,
     termlex_path: Option<PathBuf>,
     doclex_path: Option<PathBuf>,
+    scores_path: Option<PathBuf>,
+    impact_params: Option<Bm25Params>,
+    impact_bits: u8,
+    recursive_graph_bisection: bool,
+    progress_callback: Option<Arc<dyn Fn(u64, u64) + Send + Sync>>,
+}
+
+impl Default for CiffToPisa {
+    fn default() -> Self {
+        Self {
+            input: None,
+            documents_path: None,
+            frequencies_path: None,
+            sizes_path: None,
+            terms_path: None,
+            titles_path: None,
+            termlex_path: None,
+            doclex_path: None,
+            scores_path: None,
+            impact_params: None,
+            impact_bits: 8,
+            recursive_graph_bisection: false,
+            progress_callback: None,
+        }
+    }
+}
+
+impl fmt::Debug for CiffToPisa {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CiffToPisa")
+            .field("input", &self.input)
+            .field("documents_path", &self.documents_path)
+            .field("frequencies_path", &self.frequencies_path)
+            .field("sizes_path", &self.sizes_path)
+            .field("terms_path", &self.terms_path)
+            .field("titles_path", &self.titles_path)
+            .field("termlex_path", &self.termlex_path)
+            .field("doclex_path", &self.doclex_path)
+            .field("scores_path", &self.scores_path)
+            .field("impact_params", &self.impact_params)
+            .field("impact_bits", &self.impact_bits)
+            .field(
+                "recursive_graph_bisection",
+                &self.recursive_graph_bisection,
+            )
+            .field("progress_callback", &self.progress_callback.is_some())
+            .finish()
+    }
 }
 
 impl CiffToPisa {
@@ -351,6 +714,7 @@ impl CiffToPisa {
         self.titles_path = Some(paths.titles);
         self.termlex_path = paths.termlex;
         self.doclex_path = paths.doclex;
+        self.scores_path = Some(paths.index.scores);
         self
     }
 
@@ -361,6 +725,54 @@ impl CiffToPisa {
         self
     }
 
+    /// Compute quantized BM25 impact scores, using `k1`/`b` as the term-weighting parameters,
+    /// and write them into a `bits`-wide `.scores` binary collection parallel to `.docs`.
+    ///
+    /// This does not alter `.freqs`, which keeps holding raw term frequencies; the impacts are
+    /// BM25 scores computed from those frequencies, the document lengths in `.sizes`, and the
+    /// collection statistics in the CIFF header (`num_docs`, `average_doclength`), then
+    /// linearly scaled from `[0, max impact]` onto `[1, 2.pow(bits) - 1]`. This requires two
+    /// extra passes over the CIFF file: one to collect document lengths, and one to find the
+    /// impact range before the actual conversion; both endpoints of that range are printed so
+    /// the quantization can be reproduced later.
+    pub fn with_quantized_scores(&mut self, bits: u8, k1: f32, b: f32) -> &mut Self {
+        self.impact_params = Some(Bm25Params { k1, b });
+        self.impact_bits = bits;
+        self
+    }
+
+    /// Compute quantized BM25 impact scores, using `params` as the term-weighting parameters.
+    ///
+    /// Shorthand for [`CiffToPisa::with_quantized_scores`] at the default width of 8 bits.
+    pub fn quantize_impacts(&mut self, params: Bm25Params) -> &mut Self {
+        self.with_quantized_scores(8, params.k1, params.b)
+    }
+
+    /// Reorder documents via recursive graph bisection (the "BP" algorithm) before lexicons
+    /// are built.
+    ///
+    /// Treating the index as a bipartite term-document graph, this recursively splits the
+    /// document id space in two and swaps documents across the boundary to group documents
+    /// that share terms close together, which shrinks the delta-gap encodings PISA builds on
+    /// top of this uncompressed collection. `.docs`, `.freqs`, `.scores` (if present),
+    /// `.sizes`, and the titles file are all reordered to match.
+    pub fn recursive_graph_bisection(&mut self, enabled: bool) -> &mut Self {
+        self.recursive_graph_bisection = enabled;
+        self
+    }
+
+    /// Registers a callback invoked after each posting list is converted, with the number of
+    /// posting lists processed so far and the total count taken from the CIFF header. Useful
+    /// for reporting progress from a host language that can't see the `eprintln!`-based progress
+    /// bar this prints to the terminal, e.g. the `pyciff` Python bindings.
+    pub fn on_progress<F>(&mut self, callback: F) -> &mut Self
+    where
+        F: Fn(u64, u64) + Send + Sync + 'static,
+    {
+        self.progress_callback = Some(Arc::new(callback));
+        self
+    }
+
     /// Builds a PISA index using the previously defined parameters.
     ///
     /// # Errors
@@ -387,6 +799,10 @@ impl CiffToPisa {
                 .sizes_path
                 .clone()
                 .ok_or_else(|| anyhow!("document sizes path undefined"))?,
+            scores: self
+                .scores_path
+                .clone()
+                .ok_or_else(|| anyhow!("scores path undefined"))?,
         };
         let output = PisaPaths {
             index: index_output,
@@ -401,7 +817,14 @@ impl CiffToPisa {
             termlex: self.termlex_path.clone(),
             doclex: self.doclex_path.clone(),
         };
-        convert_to_pisa(input, &output)
+        convert_to_pisa(
+            input,
+            &output,
+            self.impact_params,
+            self.impact_bits,
+            self.recursive_graph_bisection,
+            self.progress_callback.clone(),
+        )
     }
 }
 
@@ -425,16 +848,125 @@ pub fn ciff_to_pisa(input: &Path, output: &Path, generate_lexicons: bool) -> Res
     converter.convert()
 }
 
-fn convert_to_pisa(input: &Path, output: &PisaPaths) -> Result<()> {
+/// Reads the document lengths off `input`'s document records, indexed by docid, along with the
+/// collection's document count and average document length already stored in the header. Used
+/// to build the [`ImpactContext`] for [`CiffToPisa::quantize_impacts`].
+///
+/// # Errors
+///
+/// Returns an error if an I/O or protobuf parsing error occurs, or if a docid is out of range.
+fn doc_lengths(input: &Path) -> Result<(Vec<i32>, u32, f64)> {
+    let mut ciff_reader =
+        File::open(input).with_context(|| format!("Unable to open {}", input.display()))?;
+    let mut coded_input = CodedInputStream::new(&mut ciff_reader);
+    let mut records = CiffRecords::new(&mut coded_input)?;
+    for posting_list in records.postings_lists() {
+        posting_list?;
+    }
+    let num_docs = records.header().num_documents;
+    let avg_doc_length = records.header().protobuf_header.get_average_doclength();
+    let mut lengths = vec![0_i32; num_docs as usize];
+    for doc_record in records.doc_records() {
+        let doc_record = doc_record?;
+        let docid = doc_record
+            .get_docid()
+            .to_usize()
+            .ok_or_else(|| anyhow!("Cannot cast docid to usize: {}", doc_record.get_docid()))?;
+        *lengths
+            .get_mut(docid)
+            .ok_or_else(|| anyhow!("Docid {docid} is out of range"))? = doc_record.get_doclength();
+    }
+    Ok((lengths, num_docs, avg_doc_length))
+}
+
+/// Scans every posting in `input` and returns the `(min, max)` BM25 impact score, for use as
+/// the scaling factor (and, printed back to the user, as a record of the range that was
+/// quantized away) in [`CiffToPisa::with_quantized_scores`].
+///
+/// # Errors
+///
+/// Returns an error if an I/O or protobuf parsing error occurs, or if the file has no postings.
+fn impact_range(
+    input: &Path,
+    doc_lengths: &[i32],
+    num_docs: u32,
+    avg_doc_length: f64,
+    params: Bm25Params,
+) -> Result<(f64, f64)> {
+    let mut ciff_reader =
+        File::open(input).with_context(|| format!("Unable to open {}", input.display()))?;
+    let mut coded_input = CodedInputStream::new(&mut ciff_reader);
+    let mut records = CiffRecords::new(&mut coded_input)?;
+
+    let mut min = f64::MAX;
+    let mut max = 0.0_f64;
+    for posting_list in records.postings_lists() {
+        let posting_list = posting_list?;
+        let df = posting_list.get_df();
+        let mut docid = 0_i32;
+        for posting in posting_list.get_postings() {
+            docid += posting.get_docid();
+            let doc_length = doc_length_or_avg(doc_lengths, avg_doc_length, docid);
+            let impact = bm25_impact(
+                i64::from(posting.get_tf()),
+                df,
+                doc_length,
+                num_docs,
+                avg_doc_length,
+                params,
+            );
+            min = min.min(impact);
+            max = max.max(impact);
+        }
+    }
+    anyhow::ensure!(max > 0.0, "No postings found for quantization");
+    Ok((min, max))
+}
+
+fn convert_to_pisa(
+    input: &Path,
+    output: &PisaPaths,
+    impact_params: Option<Bm25Params>,
+    impact_bits: u8,
+    recursive_graph_bisection: bool,
+    progress_callback: Option<Arc<dyn Fn(u64, u64) + Send + Sync>>,
+) -> Result<()> {
     println!("{output:?}");
+
+    let impact_context = if let Some(params) = impact_params {
+        eprintln!("Collecting document lengths for impact quantization");
+        let (doc_lengths, num_docs, avg_doc_length) = doc_lengths(input)?;
+        eprintln!("Finding BM25 impact range for quantization");
+        let (min_impact, max_impact) =
+            impact_range(input, &doc_lengths, num_docs, avg_doc_length, params)?;
+        eprintln!(
+            "BM25 impact range: {min_impact} to {max_impact} (quantizing into {impact_bits} bits)"
+        );
+        Some(ImpactContext {
+            doc_lengths,
+            num_docs,
+            avg_doc_length,
+            max_impact,
+            bits: impact_bits,
+            params,
+        })
+    } else {
+        None
+    };
+
     let mut ciff_reader =
         File::open(input).with_context(|| format!("Unable to open {}", input.display()))?;
     let mut input = CodedInputStream::new(&mut ciff_reader);
     let mut documents = BufWriter::new(File::create(&output.index.documents)?);
     let mut frequencies = BufWriter::new(File::create(&output.index.frequencies)?);
     let mut terms = BufWriter::new(File::create(&output.terms)?);
+    let mut scores = impact_context
+        .is_some()
+        .then(|| File::create(&output.index.scores).map(BufWriter::new))
+        .transpose()?;
 
-    let header = Header::from_stream(&mut input)?;
+    let mut records = CiffRecords::new(&mut input)?;
+    let header = records.header().clone();
     println!("{header}");
 
     eprintln!("Processing postings");
@@ -442,20 +974,30 @@ fn convert_to_pisa(input: &Path, output: &PisaPaths) -> Result<()> {
     let progress = ProgressBar::new(u64::from(header.num_postings_lists));
     progress.set_style(pb_style());
     progress.set_draw_delta(10);
-    for _ in 0..header.num_postings_lists {
+    for (postings_seen, posting_list) in records.postings_lists().enumerate() {
         write_posting_list(
-            &input.read_message::<PostingsList>()?,
+            &posting_list?,
             &mut documents,
             &mut frequencies,
             &mut terms,
+            scores
+                .as_mut()
+                .zip(impact_context.as_ref())
+                .map(|(writer, ctx)| (writer as &mut dyn Write, ctx)),
         )?;
         progress.inc(1);
+        if let Some(callback) = &progress_callback {
+            callback(postings_seen as u64 + 1, u64::from(header.num_postings_lists));
+        }
     }
     progress.finish();
 
     documents.flush()?;
     frequencies.flush()?;
     terms.flush()?;
+    if let Some(scores) = scores.as_mut() {
+        scores.flush()?;
+    }
 
     eprintln!("Processing document lengths");
     let mut sizes = BufWriter::new(File::create(&output.index.sizes)?);
@@ -467,8 +1009,9 @@ fn convert_to_pisa(input: &Path, output: &PisaPaths) -> Result<()> {
     sizes.write_all(&header.num_documents.to_le_bytes())?;
     sizes.flush()?;
 
-    for docs_seen in 0..header.num_documents {
-        let doc_record = input.read_message::<DocRecord>()?;
+    for (docs_seen, doc_record) in records.doc_records().enumerate() {
+        let doc_record = doc_record?;
+        let docs_seen = docs_seen as u32;
 
         let docid: u32 = doc_record
             .get_docid()
@@ -494,12 +1037,18 @@ fn convert_to_pisa(input: &Path, output: &PisaPaths) -> Result<()> {
     trecids.flush()?;
     progress.finish();
 
+    drop(trecids);
+
     if !check_lines_sorted(BufReader::new(File::open(&output.terms)?))? {
         reorder_pisa_index(output)?;
     }
 
+    if recursive_graph_bisection {
+        eprintln!("Computing recursive graph bisection document order");
+        reorder_documents_bp(output)?;
+    }
+
     eprintln!("Generating the document and term lexicons...");
-    drop(trecids);
     if let Some(termlex) = output.termlex.as_ref() {
         build_lexicon(&output.terms, termlex)?;
     }
@@ -628,6 +1177,8 @@ pub struct PisaToCiff {
     titles_path: Option<PathBuf>,
     output_path: Option<PathBuf>,
     description: String,
+    scores_path: Option<PathBuf>,
+    quantize_impacts: bool,
 }
 
 impl PisaToCiff {
@@ -652,6 +1203,7 @@ impl PisaToCiff {
         self.sizes_path = Some(paths.index.sizes);
         self.terms_path = Some(paths.terms);
         self.titles_path = Some(paths.titles);
+        self.scores_path = Some(paths.index.scores);
         self
     }
 
@@ -664,10 +1216,12 @@ impl PisaToCiff {
             documents,
             frequencies,
             sizes,
+            scores,
         } = PisaIndexPaths::from_base_path(base_path);
         self.documents_path = Some(documents);
         self.frequencies_path = Some(frequencies);
         self.sizes_path = Some(sizes);
+        self.scores_path = Some(scores);
         self
     }
 
@@ -689,6 +1243,14 @@ impl PisaToCiff {
         self
     }
 
+    /// Read quantized BM25 impact scores from the `.scores` file (written by
+    /// [`CiffToPisa::quantize_impacts`]) instead of `.freqs`, producing a CIFF file whose
+    /// `Posting.tf` field holds quantized impacts rather than raw term frequencies.
+    pub fn quantize_impacts(&mut self, quantize_impacts: bool) -> &mut Self {
+        self.quantize_impacts = quantize_impacts;
+        self
+    }
+
     /// Builds a CIFF index using the previously defined parameters.
     ///
     /// # Errors
@@ -698,13 +1260,20 @@ impl PisaToCiff {
     ///  - any I/O error occurs during reading input files or writing to the output file,
     ///  - any input file is in an incorrect format.
     pub fn convert(&self) -> Result<()> {
+        let frequencies_path = if self.quantize_impacts {
+            self.scores_path
+                .as_ref()
+                .ok_or_else(|| anyhow!("undefined scores path"))?
+        } else {
+            self.frequencies_path
+                .as_ref()
+                .ok_or_else(|| anyhow!("undefined frequency postings path"))?
+        };
         pisa_to_ciff_from_paths(
             self.documents_path
                 .as_ref()
                 .ok_or_else(|| anyhow!("undefined document postings path"))?,
-            self.frequencies_path
-                .as_ref()
-                .ok_or_else(|| anyhow!("undefined frequency postings path"))?,
+            frequencies_path,
             self.sizes_path
                 .as_ref()
                 .ok_or_else(|| anyhow!("undefined document sizes path"))?,
@@ -780,231 +1349,112 @@ fn pisa_to_ciff_from_paths(
     Ok(())
 }
 
-#[derive(Debug, Deserialize)]
-struct JsonDoc {
-    /// Collection docid. CIFF will automatically assign an integer ID to this.
-    /// Can be provided as either string or integer, will be converted to string.
-    #[serde(default)]
-    #[serde(deserialize_with = "deserialize_id_to_string")]
-    id: String,
-
-    /// Optional textual content for the document.
-    #[serde(default)]
-    _content: String,
-
-    /// A dictionary from token (term) to a score (e.g., frequency). This is optional in the JSON.
-    #[serde(default)]
-    vector: HashMap<String, f64>,
-}
-
-fn deserialize_id_to_string<'de, D>(deserializer: D) -> std::result::Result<String, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    use serde::de::Error;
-    use serde_json::Value;
-
-    match Value::deserialize(deserializer)
-        .map_err(|e| Error::custom(format!("failed to deserialize id: {e}")))?
-    {
-        Value::String(s) => Ok(s),
-        Value::Number(n) => {
-            if n.is_i64() || n.is_u64() {
-                Ok(n.to_string())
-            } else {
-                Err(Error::custom("id must be an integer"))
-            }
-        },
-        _ => Err(Error::custom(
-            "id must be a string or a number, but found an unsupported type",
-        )),
-    }
-}
-
-/// PISA to CIFF converter.
+/// Merges several CIFF shards into a single CIFF index.
+///
+/// Each shard is expected to encode statistics "as if a chunk of a larger index" (see the
+/// caveat in `test_to_and_from_ciff`); `MergeCiff` concatenates their document records under
+/// renumbered ids, unions their term dictionaries (merging postings lists for shared terms
+/// in document-id order, using each shard's docid offset), and recomputes the header counts
+/// across all shards.
 #[derive(Debug, Default, Clone)]
-pub struct JsonlToCiff {
-    input: Option<PathBuf>,
+pub struct MergeCiff {
+    inputs: Vec<PathBuf>,
     output: Option<PathBuf>,
-    quantize: bool,
 }
 
-impl JsonlToCiff {
-    /// Set the path of the JSONL file. Required.
-    pub fn input_path<P: Into<PathBuf>>(&mut self, path: P) -> &mut Self {
-        self.input = Some(path.into());
+impl MergeCiff {
+    /// Sets the CIFF shard paths to merge, in the order their documents should be
+    /// concatenated. Required.
+    pub fn input_paths<P, I>(&mut self, paths: I) -> &mut Self
+    where
+        P: Into<PathBuf>,
+        I: IntoIterator<Item = P>,
+    {
+        self.inputs = paths.into_iter().map(Into::into).collect();
         self
     }
 
-    /// Set the output CIFF file path. Required.
+    /// Sets the merged CIFF output path. Required.
     pub fn output_path<P: Into<PathBuf>>(&mut self, path: P) -> &mut Self {
         self.output = Some(path.into());
         self
     }
 
-    /// Set whether to quantize scores to integers.
-    /// If false, scores are assumed to be already pre-quantized and are cast directly to integers.
-    /// If true, performs 8-bit scalar quantization by mapping the score range [min, max] to [1, 256].
-    /// Default is false.
-    pub fn quantize(&mut self, quantize: bool) -> &mut Self {
-        self.quantize = quantize;
-        self
-    }
-
-    /// Performs the conversion from JSONL to CIFF.
+    /// Merges the configured CIFF shards into a single CIFF file.
     ///
     /// # Errors
     ///
-    /// If the input / output is invalid or any I/O / parsing error occurs,
-    /// returns an error.
-    pub fn convert(&self) -> Result<()> {
-        let input_path = self
-            .input
-            .as_ref()
-            .ok_or_else(|| anyhow!("No JSON input path was set"))?;
+    /// Returns an error if no input shards or no output path were set, if an I/O or protobuf
+    /// parsing error occurs, if a shard's postings lists are not in ascending term order, or
+    /// if two shards share a collection docid.
+    pub fn merge(&self) -> Result<()> {
         let output_path = self
             .output
             .as_ref()
-            .ok_or_else(|| anyhow!("No CIFF output path was set"))?;
-
-        // Open the JSONL file
-        let input_file =
-            File::open(input_path).with_context(|| format!("Cannot open {input_path:?}"))?;
-        let total_input_size = input_file.metadata()?.len();
-
-        // If quantization is enabled, we need to find min/max first
-        let (min_score, max_score) = if self.quantize {
-            eprintln!("Finding score range for quantization");
-            let reader = BufReader::new(&input_file);
-            let mut min_val = f64::INFINITY;
-            let mut max_val = f64::NEG_INFINITY;
-
-            let pb = ProgressBar::new(total_input_size);
-            pb.set_style(pb_style());
-            for line_result in reader.lines() {
-                let line = line_result?;
-                let jdoc: JsonDoc = serde_json::from_str(&line)
-                    .map_err(|e| anyhow!("Invalid JSON line:\n  `{}`\n  Error: {}", line, e))?;
-
-                for (_, score) in jdoc.vector {
-                    if score > 0.0 {
-                        // Only consider positive scores
-                        min_val = min_val.min(score);
-                        max_val = max_val.max(score);
-                    }
-                }
-                pb.inc(line.len() as u64 + 1);
-            }
-            pb.finish();
-
-            if min_val.is_infinite() || max_val.is_infinite() {
-                return Err(anyhow!("No valid scores found for quantization"));
-            }
-
-            eprintln!("Score range: {min_val} to {max_val}");
-            (min_val, max_val)
-        } else {
-            (0.0, 0.0) // Not used when quantize is false
-        };
-
-        // Reopen the file for the actual processing
-        let input_file =
-            File::open(input_path).with_context(|| format!("Cannot open {input_path:?}"))?;
-        let reader = BufReader::new(input_file);
+            .ok_or_else(|| anyhow!("output path undefined"))?;
+        anyhow::ensure!(
+            !self.inputs.is_empty(),
+            "at least one input shard is required"
+        );
 
-        // We'll store doc-level info:
+        let mut merged_terms: BTreeMap<String, (i64, i64, Vec<(i32, i32)>)> = BTreeMap::new();
         let mut doc_records: Vec<DocRecord> = Vec::new();
-
-        // We'll map "term" -> (docid, tf).
-        // Because CIFF uses integer tf, we convert scores to i32.
-        let mut postings_map: HashMap<String, Vec<(i32, i32)>> = HashMap::new();
-
+        let mut seen_titles: HashSet<String> = HashSet::new();
+        let mut docid_offset: i32 = 0;
         let mut total_terms_in_collection: i64 = 0;
 
-        // Map from a string "collection" docid to an internal integer docid.
-        let mut docid_map: HashMap<String, i32> = HashMap::new();
-        let mut current_docid: i32 = -1;
-        let mut max_docid: i32 = -1;
-
-        // Read JSON lines
-        eprintln!("Read JSON lines");
-        let pb = ProgressBar::new(total_input_size);
-        pb.set_style(pb_style());
-        for line_result in reader.lines() {
-            let line = line_result?;
-            let jdoc: JsonDoc = serde_json::from_str(&line)
-                .map_err(|e| anyhow!("Invalid JSON line:\n  `{}`\n  Error: {}", line, e))?;
-
-            // map to integer docid
-            let ciff_docid = match docid_map.get(&jdoc.id) {
-                Some(&docid) => docid,
-                None => {
-                    current_docid += 1;
-                    docid_map.insert(jdoc.id.clone(), current_docid);
-                    current_docid
+        for input_path in &self.inputs {
+            let mut reader = File::open(input_path)
+                .with_context(|| format!("Unable to open {}", input_path.display()))?;
+            let mut input = CodedInputStream::new(&mut reader);
+            let header = Header::from_stream(&mut input)?;
+
+            let mut last_term: Option<String> = None;
+            for _ in 0..header.num_postings_lists {
+                let posting_list = input.read_message::<PostingsList>()?;
+                let term = posting_list.get_term().to_string();
+                if let Some(last) = &last_term {
+                    anyhow::ensure!(
+                        *last <= term,
+                        "Shard {} has postings lists out of term order: `{}` after `{}`",
+                        input_path.display(),
+                        term,
+                        last
+                    );
                 }
-            };
-
-            if ciff_docid > max_docid {
-                max_docid = ciff_docid;
-            }
-
-            // Sum of tf's in this doc => doc_length
-            let mut doc_length = 0i64;
-            for (term, score) in jdoc.vector {
-                let tf = if self.quantize {
-                    // 8-bit scalar quantization: map [min_score, max_score] to [1, 256]
-                    // We use 1-256 to avoid zero values which get filtered out
-                    if score <= 0.0 {
-                        0 // Will be filtered out below
-                    } else {
-                        let normalized = (score - min_score) / (max_score - min_score);
-                        let quantization_range = MAX_QUANTIZED_VALUE - MIN_QUANTIZED_VALUE;
-                        let quantized = (normalized * quantization_range as f64
-                            + MIN_QUANTIZED_VALUE as f64)
-                            .round() as i32;
-                        quantized.clamp(MIN_QUANTIZED_VALUE, MAX_QUANTIZED_VALUE)
-                    }
-                } else {
-                    // Assume scores are already pre-quantized integers
-                    score as i32
-                };
-
-                if tf <= 0 {
-                    continue; // skip zero or negative
+                last_term = Some(term.clone());
+
+                let entry = merged_terms
+                    .entry(term)
+                    .or_insert_with(|| (0_i64, 0_i64, Vec::new()));
+                let mut docid = docid_offset;
+                for posting in posting_list.get_postings() {
+                    docid += posting.get_docid();
+                    entry.2.push((docid, posting.get_tf()));
+                    entry.0 += 1;
+                    entry.1 += i64::from(posting.get_tf());
                 }
-                doc_length += 1;
-
-                postings_map.entry(term).or_default().push((ciff_docid, tf));
             }
-            total_terms_in_collection += doc_length;
 
-            // Build a DocRecord
-            let mut record = DocRecord::new();
-            record.set_docid(ciff_docid);
-            record.set_collection_docid(jdoc.id);
-            record.set_doclength(doc_length as i32);
+            for _ in 0..header.num_documents {
+                let mut record = input.read_message::<DocRecord>()?;
+                let title = record.get_collection_docid().to_string();
+                anyhow::ensure!(
+                    seen_titles.insert(title.clone()),
+                    "Document id `{title}` appears in more than one shard"
+                );
+                total_terms_in_collection += i64::from(record.get_doclength());
+                record.set_docid(record.get_docid() + docid_offset);
+                doc_records.push(record);
+            }
 
-            doc_records.push(record);
-            pb.inc(line.len() as u64 + 1);
+            docid_offset += i32::try_from(header.num_documents)
+                .context("Number of documents must fit in an i32")?;
         }
-        pb.finish();
 
-        // Sort doc_records by docid
-        doc_records.sort_by_key(DocRecord::get_docid);
         let num_docs = doc_records.len() as i32;
+        let num_postings_lists = merged_terms.len() as i32;
 
-        // Build postings (term -> PostingsList)
-        // 1) collect (docid, tf)
-        // 2) sort by docid
-        // 3) store into a PostingsList with df/cf
-        let mut terms: Vec<(String, Vec<(i32, i32)>)> = postings_map.into_iter().collect();
-        // Sort by term lex order (not required by CIFF, but common)
-        terms.sort_by(|a, b| a.0.cmp(&b.0));
-
-        let num_postings_lists = terms.len() as i32;
-
-        // Build the CIFF Header
         let mut header = proto::Header::default();
         header.set_version(1);
         header.set_num_postings_lists(num_postings_lists);
@@ -1012,73 +1462,1543 @@ impl JsonlToCiff {
         header.set_num_docs(num_docs);
         header.set_total_docs(num_docs);
         header.set_total_terms_in_collection(total_terms_in_collection);
-        if num_docs > 0 {
-            header.set_average_doclength(total_terms_in_collection as f64 / f64::from(num_docs));
+        header.set_average_doclength(if num_docs > 0 {
+            total_terms_in_collection as f64 / f64::from(num_docs)
         } else {
-            header.set_average_doclength(0.0);
-        }
-        header.set_description("Converted from JSON lines".to_string());
+            0.0
+        });
+        header.set_description(format!("Merged from {} CIFF shards", self.inputs.len()));
+
+        let output_file = File::create(output_path)
+            .with_context(|| format!("Cannot create output file {}", output_path.display()))?;
+        let mut writer = BufWriter::new(output_file);
+        let mut out = CodedOutputStream::new(&mut writer);
+        out.write_message_no_tag(&header)?;
+
+        for (term, (df, cf, postings)) in merged_terms {
+            let mut posting_list = PostingsList::default();
+            posting_list.set_term(term);
+            posting_list.set_df(df);
+            posting_list.set_cf(cf);
+            let mut last_doc = 0;
+            for (docid, tf) in postings {
+                let mut posting = Posting::default();
+                posting.set_docid(docid - last_doc);
+                posting.set_tf(tf);
+                posting_list.postings.push(posting);
+                last_doc = docid;
+            }
+            out.write_message_no_tag(&posting_list)?;
+        }
+
+        for record in doc_records {
+            out.write_message_no_tag(&record)?;
+        }
+
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// A single internal-consistency problem found by [`CiffValidator`], along with enough
+/// context (term or document position) to locate it without re-reading the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue(String);
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The outcome of [`CiffValidator::validate`]: every issue found while scanning the file,
+/// in the order encountered. An empty report means the file is internally consistent.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Returns `true` if no issues were found.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Returns the issues found, in the order encountered.
+    #[must_use]
+    pub fn issues(&self) -> &[ValidationIssue] {
+        &self.issues
+    }
+
+    fn push(&mut self, issue: impl Into<String>) {
+        self.issues.push(ValidationIssue(issue.into()));
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.issues.is_empty() {
+            write!(f, "CIFF file is valid")
+        } else {
+            for issue in &self.issues {
+                writeln!(f, "{issue}")?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Checks a CIFF file for internal consistency without converting it to another format.
+///
+/// Unlike the other converters in this crate, [`CiffValidator::validate`] does not bail out
+/// on the first problem it finds: it scans the whole file and returns every issue it saw, so
+/// it is useful as an inspection tool on its own.
+#[derive(Debug, Default, Clone)]
+pub struct CiffValidator {
+    input: Option<PathBuf>,
+}
+
+impl CiffValidator {
+    /// Sets the CIFF file to validate. Required.
+    pub fn input_path<P: Into<PathBuf>>(&mut self, path: P) -> &mut Self {
+        self.input = Some(path.into());
+        self
+    }
+
+    /// Scans the configured CIFF file and reports every internal-consistency issue found:
+    /// postings lists out of term order, postings out of docid order within a list, `df`/`cf`
+    /// that disagree with the actual postings, document records out of docid order or with a
+    /// non-positive length, the header's `num_postings_lists`/`num_docs` disagreeing with the
+    /// number of postings lists/document records actually read, and the header's
+    /// `average_doclength` disagreeing with the average recomputed from those document
+    /// records.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no input path was set, or if an I/O or protobuf parsing error
+    /// occurs. Internal-consistency problems are *not* returned as errors; they are
+    /// collected into the returned [`ValidationReport`].
+    pub fn validate(&self) -> Result<ValidationReport> {
+        let input_path = self
+            .input
+            .as_ref()
+            .ok_or_else(|| anyhow!("input path undefined"))?;
+        let mut reader = File::open(input_path)
+            .with_context(|| format!("Unable to open {}", input_path.display()))?;
+        let mut input = CodedInputStream::new(&mut reader);
+        let mut records = CiffRecords::new(&mut input)?;
+
+        let mut report = ValidationReport::default();
+        let header = records.header().clone();
+
+        let mut last_term: Option<String> = None;
+        let mut num_postings_lists_scanned: u32 = 0;
+        for posting_list in records.postings_lists() {
+            let posting_list = posting_list?;
+            let term = posting_list.get_term().to_string();
+            if let Some(last) = &last_term {
+                if *last > term {
+                    report.push(format!(
+                        "Postings lists out of term order: `{term}` after `{last}`"
+                    ));
+                }
+            }
+            last_term = Some(term.clone());
+            num_postings_lists_scanned += 1;
+
+            let mut df = 0_i64;
+            let mut cf = 0_i64;
+            let mut docid = 0_i64;
+            for (i, posting) in posting_list.get_postings().iter().enumerate() {
+                if i > 0 && posting.get_docid() <= 0 {
+                    report.push(format!(
+                        "Term `{term}` has non-increasing docid gap at posting {i}"
+                    ));
+                }
+                docid += i64::from(posting.get_docid());
+                df += 1;
+                cf += i64::from(posting.get_tf());
+            }
+            if df != posting_list.get_df() {
+                report.push(format!(
+                    "Term `{term}` declares df={} but has {df} postings",
+                    posting_list.get_df()
+                ));
+            }
+            if cf != posting_list.get_cf() {
+                report.push(format!(
+                    "Term `{term}` declares cf={} but postings sum to {cf}",
+                    posting_list.get_cf()
+                ));
+            }
+            let _ = docid;
+        }
+
+        if num_postings_lists_scanned != header.num_postings_lists {
+            report.push(format!(
+                "Header declares num_postings_lists={} but {num_postings_lists_scanned} \
+                 postings lists were found",
+                header.num_postings_lists
+            ));
+        }
+
+        let mut last_docid: Option<i32> = None;
+        let mut num_documents_scanned: u32 = 0;
+        let mut doclength_sum: i64 = 0;
+        for doc_record in records.doc_records() {
+            let doc_record = doc_record?;
+            let docid = doc_record.get_docid();
+            if let Some(last) = last_docid {
+                if docid != last + 1 {
+                    report.push(format!(
+                        "Document records out of order: docid {docid} follows {last}"
+                    ));
+                }
+            } else if docid != 0 {
+                report.push(format!("First document record has docid {docid}, expected 0"));
+            }
+            last_docid = Some(docid);
+            if doc_record.get_doclength() <= 0 {
+                report.push(format!(
+                    "Document `{}` has non-positive length {}",
+                    doc_record.get_collection_docid(),
+                    doc_record.get_doclength()
+                ));
+            }
+            num_documents_scanned += 1;
+            doclength_sum += i64::from(doc_record.get_doclength());
+        }
+
+        if num_documents_scanned != header.num_documents {
+            report.push(format!(
+                "Header declares num_docs={} but {num_documents_scanned} document records were \
+                 found",
+                header.num_documents
+            ));
+        }
+
+        if num_documents_scanned > 0 {
+            #[allow(clippy::cast_precision_loss)]
+            let actual_avg_doclength = doclength_sum as f64 / f64::from(num_documents_scanned);
+            let header_avg_doclength = header.protobuf_header.get_average_doclength();
+            if (actual_avg_doclength - header_avg_doclength).abs() > 1e-3 {
+                report.push(format!(
+                    "Header declares average_doclength={header_avg_doclength} but document \
+                     records average {actual_avg_doclength}"
+                ));
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// A single term's postings, decoded into the same representation [`write_posting_list`]
+/// writes out: docids already delta-accumulated into absolute ids, alongside `df` and `cf`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedPostingsList {
+    df: i64,
+    cf: i64,
+    postings: Vec<(u32, u32)>,
+}
+
+impl DecodedPostingsList {
+    /// Number of documents containing this term.
+    #[must_use]
+    pub fn df(&self) -> i64 {
+        self.df
+    }
+
+    /// Total number of occurrences of this term across the collection.
+    #[must_use]
+    pub fn cf(&self) -> i64 {
+        self.cf
+    }
+
+    /// This term's postings as `(docid, tf)` pairs, in ascending docid order.
+    #[must_use]
+    pub fn postings(&self) -> &[(u32, u32)] {
+        &self.postings
+    }
+}
+
+/// Read-only inspection of a CIFF file: look up a single term's decoded posting list, or a
+/// document record by internal id, without running the full [`CiffToPisa`] conversion
+/// pipeline.
+///
+/// [`CiffReader::open`] decodes the whole file up front, so term lookups are `O(log n)`
+/// rather than a linear scan: it builds a sorted [`PayloadVector`] lexicon over the terms,
+/// the same machinery [`build_lexicon`] uses for PISA's `.termlex`, and binary searches it,
+/// mirroring how a segment postings reader resolves a term before returning its list. This
+/// makes the crate usable for quick debugging and ad-hoc retrieval over a CIFF file without
+/// materializing a PISA index.
+pub struct CiffReader {
+    num_documents: u32,
+    postings: Vec<DecodedPostingsList>,
+    /// Sorted lexicon of terms, parallel to `term_order`.
+    term_lexicon: PayloadVector,
+    /// `term_order[i]` is the index into `postings` of the `i`-th term in `term_lexicon`.
+    term_order: Vec<usize>,
+    doc_records: Vec<DocRecord>,
+}
+
+impl CiffReader {
+    /// Reads and fully decodes `path`'s header, posting lists, and document records.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O or protobuf parsing error occurs.
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut reader =
+            File::open(path).with_context(|| format!("Unable to open {}", path.display()))?;
+        let mut input = CodedInputStream::new(&mut reader);
+        let mut records = CiffRecords::new(&mut input)?;
+        let num_documents = records.header().num_documents;
+        let num_postings_lists = records.header().num_postings_lists as usize;
+
+        let mut terms = Vec::with_capacity(num_postings_lists);
+        let mut postings = Vec::with_capacity(num_postings_lists);
+        for posting_list in records.postings_lists() {
+            let posting_list = posting_list?;
+            terms.push(posting_list.get_term().to_string());
+            let mut docid = 0_u32;
+            let decoded = posting_list
+                .get_postings()
+                .iter()
+                .map(|p| {
+                    docid += u32::try_from(p.get_docid()).expect("Negative ID");
+                    let tf = u32::try_from(p.get_tf()).expect("Negative frequency");
+                    (docid, tf)
+                })
+                .collect();
+            postings.push(DecodedPostingsList {
+                df: posting_list.get_df(),
+                cf: posting_list.get_cf(),
+                postings: decoded,
+            });
+        }
+
+        let mut term_order: Vec<usize> = (0..terms.len()).collect();
+        term_order.sort_by_key(|&i| &terms[i]);
+        let term_lexicon: PayloadVector = term_order.iter().map(|&i| &terms[i]).collect();
+
+        let doc_records = records.doc_records().collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            num_documents,
+            postings,
+            term_lexicon,
+            term_order,
+            doc_records,
+        })
+    }
+
+    /// Looks up `term`'s decoded posting list, or `None` if it does not appear in the
+    /// collection.
+    #[must_use]
+    pub fn term(&self, term: &str) -> Option<&DecodedPostingsList> {
+        let position = binary_search_lexicon(&self.term_lexicon, term.as_bytes())?;
+        self.postings.get(self.term_order[position])
+    }
+
+    /// Returns the document record at internal id `docid`, or `None` if out of range.
+    #[must_use]
+    pub fn doc_record(&self, docid: usize) -> Option<&DocRecord> {
+        self.doc_records.get(docid)
+    }
+
+    /// Number of documents in the collection.
+    #[must_use]
+    pub fn num_documents(&self) -> u32 {
+        self.num_documents
+    }
+}
+
+/// Binary searches a [`PayloadSlice`]-backed lexicon built from entries in sorted order, as
+/// [`CiffReader::open`] builds `term_lexicon`, returning the matching entry's position.
+fn binary_search_lexicon(lexicon: &PayloadSlice, key: &[u8]) -> Option<usize> {
+    let mut lo = 0_u64;
+    let mut hi = lexicon.len();
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        match lexicon.get(mid)?.cmp(key) {
+            std::cmp::Ordering::Less => lo = mid + 1,
+            std::cmp::Ordering::Greater => hi = mid,
+            std::cmp::Ordering::Equal => return Some(mid as usize),
+        }
+    }
+    None
+}
+
+#[derive(Debug, Serialize)]
+struct JsonlDoc<'a> {
+    id: &'a str,
+    vector: BTreeMap<&'a str, f64>,
+}
+
+/// CIFF to JSONL converter.
+///
+/// Emits one JSON object per document, each holding the document's collection id and its
+/// term vector (term to frequency), i.e. the same shape consumed by [`JsonlToCiff`]. This
+/// makes the two builders a matched round trip: a [`JsonlToCiff`]-built CIFF can be
+/// exported back with `CiffToJsonl` and re-imported with [`JsonlToCiff`].
+#[derive(Debug, Default, Clone)]
+pub struct CiffToJsonl {
+    input: Option<PathBuf>,
+    output: Option<PathBuf>,
+}
+
+impl CiffToJsonl {
+    /// Set the path of the CIFF file. Required.
+    pub fn input_path<P: Into<PathBuf>>(&mut self, path: P) -> &mut Self {
+        self.input = Some(path.into());
+        self
+    }
+
+    /// Set the output JSONL file path. Required.
+    pub fn output_path<P: Into<PathBuf>>(&mut self, path: P) -> &mut Self {
+        self.output = Some(path.into());
+        self
+    }
+
+    /// Performs the conversion from CIFF to JSONL.
+    ///
+    /// # Errors
+    ///
+    /// If the input / output is invalid or any I/O / parsing error occurs, or the CIFF file
+    /// does not list document records in ascending docid order, returns an error.
+    pub fn convert(&self) -> Result<()> {
+        let input_path = self
+            .input
+            .as_ref()
+            .ok_or_else(|| anyhow!("No CIFF input path was set"))?;
+        let output_path = self
+            .output
+            .as_ref()
+            .ok_or_else(|| anyhow!("No JSONL output path was set"))?;
+
+        let mut ciff_reader = File::open(input_path)
+            .with_context(|| format!("Unable to open {}", input_path.display()))?;
+        let mut input = CodedInputStream::new(&mut ciff_reader);
+        let header = Header::from_stream(&mut input)?;
+
+        let mut vectors: Vec<HashMap<String, f64>> =
+            vec![HashMap::new(); header.num_documents as usize];
+        for _ in 0..header.num_postings_lists {
+            let posting_list = input.read_message::<PostingsList>()?;
+            let mut docid = 0_i32;
+            for posting in posting_list.get_postings() {
+                docid += posting.get_docid();
+                let vector = vectors.get_mut(docid as usize).ok_or_else(|| {
+                    anyhow!("Document id {docid} in posting list is out of range")
+                })?;
+                vector.insert(posting_list.get_term().to_string(), f64::from(posting.get_tf()));
+            }
+        }
 
-        // Open output CIFF file
         let output_file = File::create(output_path)
             .with_context(|| format!("Cannot create output file {output_path:?}"))?;
         let mut writer = BufWriter::new(output_file);
-        let mut coded_out = CodedOutputStream::new(&mut writer);
 
-        // 1) Write header
-        coded_out.write_message_no_tag(&header)?;
+        for docs_seen in 0..header.num_documents {
+            let doc_record = input.read_message::<DocRecord>()?;
+            let docid = doc_record
+                .get_docid()
+                .to_u32()
+                .ok_or_else(|| anyhow!("Cannot cast docid to u32: {}", doc_record.get_docid()))?;
+            if docid != docs_seen {
+                anyhow::bail!("Document records must come in order");
+            }
+            let doc = JsonlDoc {
+                id: doc_record.get_collection_docid(),
+                vector: vectors[docid as usize]
+                    .iter()
+                    .map(|(term, score)| (term.as_str(), *score))
+                    .collect(),
+            };
+            serde_json::to_writer(&mut writer, &doc)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonDoc {
+    /// Collection docid. CIFF will automatically assign an integer ID to this.
+    /// Can be provided as either string or integer, will be converted to string.
+    #[serde(default)]
+    #[serde(deserialize_with = "deserialize_id_to_string")]
+    id: String,
+
+    /// Optional textual content for the document.
+    #[serde(default)]
+    _content: String,
+
+    /// A dictionary from token (term) to a score (e.g., frequency). This is optional in the JSON.
+    #[serde(default)]
+    vector: HashMap<String, f64>,
+}
+
+fn deserialize_id_to_string<'de, D>(deserializer: D) -> std::result::Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::de::Error;
+    use serde_json::Value;
+
+    match Value::deserialize(deserializer)
+        .map_err(|e| Error::custom(format!("failed to deserialize id: {e}")))?
+    {
+        Value::String(s) => Ok(s),
+        Value::Number(n) => {
+            if n.is_i64() || n.is_u64() {
+                Ok(n.to_string())
+            } else {
+                Err(Error::custom("id must be an integer"))
+            }
+        },
+        _ => Err(Error::custom(
+            "id must be a string or a number, but found an unsupported type",
+        )),
+    }
+}
+
+/// Input document format accepted by [`JsonlToCiff`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentFormat {
+    /// One JSON document object per line.
+    Ndjson,
+    /// A single top-level JSON array of document objects.
+    JsonArray,
+    /// CSV: a docid column followed by any number of `term:score` columns.
+    Csv,
+    /// Detect the format from the input: a leading `[` means [`DocumentFormat::JsonArray`], a
+    /// leading `{` means [`DocumentFormat::Ndjson`], and anything else falls back to
+    /// [`DocumentFormat::Csv`] if the input path has a `.csv` extension, or
+    /// [`DocumentFormat::Ndjson`] otherwise.
+    Auto,
+}
+
+impl Default for DocumentFormat {
+    fn default() -> Self {
+        Self::Auto
+    }
+}
+
+impl std::str::FromStr for DocumentFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "ndjson" => Ok(Self::Ndjson),
+            "json-array" => Ok(Self::JsonArray),
+            "csv" => Ok(Self::Csv),
+            "auto" => Ok(Self::Auto),
+            _ => Err(anyhow!(
+                "invalid document format `{s}`; expected one of: ndjson, json-array, csv, auto"
+            )),
+        }
+    }
+}
+
+/// Scalar quantization scheme for mapping document scores onto integer term frequencies, used
+/// by [`JsonlToCiff::quantization_scheme`] and [`ParquetToCiff::quantization_scheme`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuantizationScheme {
+    /// Linearly maps `[min, max]` onto `[1, 2.pow(bits) - 1]`.
+    Linear,
+    /// Maps `[ln(min), ln(max)]` onto `[1, 2.pow(bits) - 1]`. Learned-sparse weights are usually
+    /// long-tailed, so `Linear` wastes most of the output range on a handful of large scores;
+    /// `Log` spreads small positive scores across more of it instead.
+    Log,
+}
+
+impl Default for QuantizationScheme {
+    fn default() -> Self {
+        Self::Linear
+    }
+}
+
+impl std::str::FromStr for QuantizationScheme {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "linear" => Ok(Self::Linear),
+            "log" => Ok(Self::Log),
+            _ => Err(anyhow!(
+                "invalid quantization scheme `{s}`; expected one of: linear, log"
+            )),
+        }
+    }
+}
+
+/// Maps `score` from `[min, max]` (both positive) onto `[1, 2.pow(bits) - 1]` using `scheme`.
+/// Used by [`write_ciff_from_documents`]; factored out so the edge cases (degenerate range, clamping) are defined in one place.
+fn quantize_score(score: f64, (min, max): (f64, f64), bits: u8, scheme: QuantizationScheme) -> i32 {
+    let max_quantized = (1_i32 << bits) - 1;
+    if min >= max {
+        return MIN_QUANTIZED_VALUE;
+    }
+    let normalized = match scheme {
+        QuantizationScheme::Linear => (score - min) / (max - min),
+        QuantizationScheme::Log => (score.ln() - min.ln()) / (max.ln() - min.ln()),
+    };
+    let quantization_range = max_quantized - MIN_QUANTIZED_VALUE;
+    let quantized = (normalized * f64::from(quantization_range) + f64::from(MIN_QUANTIZED_VALUE))
+        .round() as i32;
+    quantized.clamp(MIN_QUANTIZED_VALUE, max_quantized)
+}
+
+/// Resolves [`DocumentFormat::Auto`] by peeking at the first non-whitespace byte of `path`.
+fn resolve_document_format(path: &Path, format: DocumentFormat) -> Result<DocumentFormat> {
+    if format != DocumentFormat::Auto {
+        return Ok(format);
+    }
+    let mut file = File::open(path).with_context(|| format!("Cannot open {path:?}"))?;
+    let mut buf = [0_u8; 512];
+    let read = file.read(&mut buf)?;
+    match buf[..read].iter().find(|byte| !byte.is_ascii_whitespace()) {
+        Some(b'[') => Ok(DocumentFormat::JsonArray),
+        Some(b'{') => Ok(DocumentFormat::Ndjson),
+        _ if path.extension() == Some(OsStr::new("csv")) => Ok(DocumentFormat::Csv),
+        _ => Ok(DocumentFormat::Ndjson),
+    }
+}
+
+/// Parses `reader` as a stream of whitespace-separated [`JsonDoc`] values (NDJSON, but also
+/// tolerant of pretty-printed or otherwise multi-line records), reporting progress against
+/// `total_size` bytes.
+fn read_ndjson_documents<R: BufRead>(reader: R, total_size: u64) -> Result<Vec<JsonDoc>> {
+    let pb = ProgressBar::new(total_size);
+    pb.set_style(pb_style());
+
+    let mut stream = serde_json::Deserializer::from_reader(reader).into_iter::<JsonDoc>();
+    let mut docs = Vec::new();
+    let mut last_offset = 0_u64;
+    while let Some(doc) = stream.next() {
+        docs.push(doc.map_err(|e| anyhow!("Invalid JSON document: {e}"))?);
+        let offset = stream.byte_offset() as u64;
+        pb.inc(offset - last_offset);
+        last_offset = offset;
+    }
+    pb.finish();
+    Ok(docs)
+}
+
+/// Parses `reader` as a single top-level JSON array of documents.
+fn read_json_array_documents<R: BufRead>(reader: R) -> Result<Vec<JsonDoc>> {
+    serde_json::from_reader(reader).map_err(|e| anyhow!("Invalid JSON array of documents: {e}"))
+}
+
+/// Parses `reader` as CSV: the first column is the docid, and every remaining column is a
+/// `term:score` pair.
+fn read_csv_documents<R: BufRead>(reader: R) -> Result<Vec<JsonDoc>> {
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(reader);
+    let mut docs = Vec::new();
+    for record in csv_reader.records() {
+        let record = record?;
+        let mut fields = record.iter();
+        let id = fields
+            .next()
+            .ok_or_else(|| anyhow!("CSV row is missing a docid column"))?
+            .to_string();
+        let mut vector = HashMap::new();
+        for field in fields {
+            let (term, score) = field
+                .split_once(':')
+                .ok_or_else(|| anyhow!("Invalid `term:score` column: `{field}`"))?;
+            vector.insert(
+                term.to_string(),
+                score
+                    .parse()
+                    .with_context(|| format!("Invalid score in `{field}`"))?,
+            );
+        }
+        docs.push(JsonDoc {
+            id,
+            _content: String::new(),
+            vector,
+        });
+    }
+    Ok(docs)
+}
+
+/// Sorts `triples` by `(term, docid)` and writes them to `path` as a run of
+/// length-prefixed `(term, docid, tf)` records, for later k-way merging by [`RunReader`].
+fn write_run(path: &Path, mut triples: Vec<(String, i32, i32)>) -> Result<()> {
+    triples.sort_by(|a, b| (a.0.as_str(), a.1).cmp(&(b.0.as_str(), b.1)));
+    let file = File::create(path).with_context(|| format!("Cannot create run file {path:?}"))?;
+    let mut writer = BufWriter::new(file);
+    for (term, docid, tf) in &triples {
+        let term_bytes = term.as_bytes();
+        writer.write_all(&(term_bytes.len() as u32).to_le_bytes())?;
+        writer.write_all(term_bytes)?;
+        writer.write_all(&docid.to_le_bytes())?;
+        writer.write_all(&tf.to_le_bytes())?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Accumulates `(term, docid, tf)` triples in memory during [`JsonlToCiff::convert`], spilling
+/// them as sorted runs (see [`write_run`]) once the buffer exceeds `max_memory` bytes. This
+/// bounds the memory held by buffered postings; the parsed input documents themselves are all
+/// held in memory by [`JsonlToCiff::convert`].
+struct PostingsSpiller {
+    dir: PathBuf,
+    /// Disambiguates run file names when several spillers (one per parallel fold in
+    /// [`JsonlToCiff::convert`]) share the same `dir`.
+    id: usize,
+    max_memory: usize,
+    buffer: Vec<(String, i32, i32)>,
+    buffer_bytes: usize,
+    runs: Vec<PathBuf>,
+}
+
+impl PostingsSpiller {
+    fn new(dir: PathBuf, id: usize, max_memory: usize) -> Self {
+        Self {
+            dir,
+            id,
+            max_memory,
+            buffer: Vec::new(),
+            buffer_bytes: 0,
+            runs: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, term: String, docid: i32, tf: i32) -> Result<()> {
+        self.buffer_bytes += term.len() + std::mem::size_of::<(i32, i32)>();
+        self.buffer.push((term, docid, tf));
+        if self.buffer_bytes >= self.max_memory {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    fn spill(&mut self) -> Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let triples = std::mem::take(&mut self.buffer);
+        self.buffer_bytes = 0;
+        let path = self
+            .dir
+            .join(format!("run-{:04}-{:06}.bin", self.id, self.runs.len()));
+        write_run(&path, triples)?;
+        self.runs.push(path);
+        Ok(())
+    }
+
+    /// Flushes any buffered triples and returns the paths of every run written so far.
+    fn finish(mut self) -> Result<Vec<PathBuf>> {
+        self.spill()?;
+        Ok(self.runs)
+    }
+}
+
+/// Reads back one run written by [`write_run`], one `(term, docid, tf)` triple at a time.
+struct RunReader {
+    reader: BufReader<File>,
+}
+
+impl RunReader {
+    fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path).with_context(|| format!("Cannot open run file {path:?}"))?;
+        Ok(Self {
+            reader: BufReader::new(file),
+        })
+    }
+
+    fn next_triple(&mut self) -> Result<Option<(String, i32, i32)>> {
+        let mut len_buf = [0_u8; 4];
+        match self.reader.read_exact(&mut len_buf) {
+            Ok(()) => {}
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(error) => return Err(error.into()),
+        }
+        let term_len = u32::from_le_bytes(len_buf) as usize;
+        let mut term_buf = vec![0_u8; term_len];
+        self.reader.read_exact(&mut term_buf)?;
+        let term = String::from_utf8(term_buf).context("Invalid UTF-8 in run file")?;
+        let mut docid_buf = [0_u8; 4];
+        self.reader.read_exact(&mut docid_buf)?;
+        let mut tf_buf = [0_u8; 4];
+        self.reader.read_exact(&mut tf_buf)?;
+        Ok(Some((
+            term,
+            i32::from_le_bytes(docid_buf),
+            i32::from_le_bytes(tf_buf),
+        )))
+    }
+}
+
+/// One run's current `(term, docid, tf)` triple, ordered for the k-way merge min-heap in
+/// [`merge_runs`] so that the lexicographically smallest `(term, docid)` sorts first.
+struct HeapEntry {
+    term: String,
+    docid: i32,
+    tf: i32,
+    run: usize,
+}
+
+impl PartialEq for HeapEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.term == other.term && self.docid == other.docid
+    }
+}
+
+impl Eq for HeapEntry {}
+
+impl PartialOrd for HeapEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HeapEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // `BinaryHeap` is a max-heap; reverse the comparison so the smallest `(term, docid)`
+        // is popped first.
+        (&other.term, other.docid).cmp(&(&self.term, self.docid))
+    }
+}
+
+/// K-way merges the sorted runs at `run_paths`, coalescing consecutive triples that share a
+/// term into one [`PostingsList`] (computing `df`/`cf` on the fly and delta-gapping `docid`s),
+/// and writes each list to `coded_out` as it completes. `progress` is incremented once per
+/// completed postings list.
+fn merge_runs(
+    run_paths: &[PathBuf],
+    coded_out: &mut CodedOutputStream,
+    progress: &ProgressBar,
+) -> Result<()> {
+    let mut readers = run_paths
+        .iter()
+        .map(|path| RunReader::open(path))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut heap = BinaryHeap::new();
+    for (run, reader) in readers.iter_mut().enumerate() {
+        if let Some((term, docid, tf)) = reader.next_triple()? {
+            heap.push(HeapEntry { term, docid, tf, run });
+        }
+    }
+
+    let mut current: Option<(String, PostingsList, i32)> = None;
+    while let Some(HeapEntry { term, docid, tf, run }) = heap.pop() {
+        if let Some((next_term, next_docid, next_tf)) = readers[run].next_triple()? {
+            heap.push(HeapEntry {
+                term: next_term,
+                docid: next_docid,
+                tf: next_tf,
+                run,
+            });
+        }
+
+        match &mut current {
+            Some((current_term, postings_list, last_docid)) if *current_term == term => {
+                let mut posting = Posting::new();
+                posting.set_docid(docid - *last_docid);
+                posting.set_tf(tf);
+                postings_list.set_df(postings_list.get_df() + 1);
+                postings_list.set_cf(postings_list.get_cf() + i64::from(tf));
+                postings_list.postings.push(posting);
+                *last_docid = docid;
+            }
+            _ => {
+                if let Some((_, postings_list, _)) = current.take() {
+                    coded_out.write_message_no_tag(&postings_list)?;
+                    progress.inc(1);
+                }
+                let mut postings_list = PostingsList::new();
+                postings_list.set_term(term.clone());
+                let mut posting = Posting::new();
+                posting.set_docid(docid);
+                posting.set_tf(tf);
+                postings_list.set_df(1);
+                postings_list.set_cf(i64::from(tf));
+                postings_list.postings.push(posting);
+                current = Some((term, postings_list, docid));
+            }
+        }
+    }
+    if let Some((_, postings_list, _)) = current.take() {
+        coded_out.write_message_no_tag(&postings_list)?;
+        progress.inc(1);
+    }
+
+    Ok(())
+}
+
+/// JSONL to CIFF converter.
+#[derive(Debug, Clone)]
+pub struct JsonlToCiff {
+    input: Option<PathBuf>,
+    output: Option<PathBuf>,
+    quantize: bool,
+    quantize_bits: u8,
+    quantization_scheme: QuantizationScheme,
+    format: DocumentFormat,
+    max_memory: usize,
+    threads: usize,
+}
+
+impl Default for JsonlToCiff {
+    fn default() -> Self {
+        Self {
+            input: None,
+            output: None,
+            quantize: false,
+            quantize_bits: 8,
+            quantization_scheme: QuantizationScheme::default(),
+            format: DocumentFormat::default(),
+            max_memory: DEFAULT_MAX_MEMORY_BYTES,
+            threads: 0,
+        }
+    }
+}
+
+impl JsonlToCiff {
+    /// Set the path of the JSONL file. Required.
+    pub fn input_path<P: Into<PathBuf>>(&mut self, path: P) -> &mut Self {
+        self.input = Some(path.into());
+        self
+    }
+
+    /// Set the output CIFF file path. Required.
+    pub fn output_path<P: Into<PathBuf>>(&mut self, path: P) -> &mut Self {
+        self.output = Some(path.into());
+        self
+    }
+
+    /// Set whether to quantize scores to integers.
+    /// If false, scores are assumed to be already pre-quantized and are cast directly to integers.
+    /// If true, performs scalar quantization by mapping the score range `[min, max]` to
+    /// `[1, 2^quantize_bits - 1]`, per [`quantize_bits`](Self::quantize_bits) and
+    /// [`quantization_scheme`](Self::quantization_scheme). Default is false.
+    pub fn quantize(&mut self, quantize: bool) -> &mut Self {
+        self.quantize = quantize;
+        self
+    }
+
+    /// Set the bit width of quantized scores, producing a range of `[1, 2^bits - 1]`. Only
+    /// takes effect when [`quantize`](Self::quantize) is set. Default is 8.
+    pub fn quantize_bits(&mut self, bits: u8) -> &mut Self {
+        self.quantize_bits = bits;
+        self
+    }
+
+    /// Set the scalar quantization scheme. Only takes effect when
+    /// [`quantize`](Self::quantize) is set. Default is [`QuantizationScheme::Linear`].
+    pub fn quantization_scheme(&mut self, scheme: QuantizationScheme) -> &mut Self {
+        self.quantization_scheme = scheme;
+        self
+    }
+
+    /// Set the input document format. Default is [`DocumentFormat::Auto`].
+    pub fn format(&mut self, format: DocumentFormat) -> &mut Self {
+        self.format = format;
+        self
+    }
+
+    /// Set the in-memory budget, in bytes, for buffered postings before they are spilled to a
+    /// run file on disk. Lower values bound the memory used by buffered postings at the cost of
+    /// more run files to merge; the parsed input documents are not covered by this budget.
+    /// Default is 256 MiB.
+    pub fn max_memory(&mut self, max_memory: usize) -> &mut Self {
+        self.max_memory = max_memory;
+        self
+    }
+
+    /// Set the number of threads used to quantize scores and build postings in parallel.
+    /// `0` (the default) lets rayon pick based on available CPUs.
+    pub fn threads(&mut self, threads: usize) -> &mut Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Performs the conversion from JSONL to CIFF.
+    ///
+    /// # Errors
+    ///
+    /// If the input / output is invalid or any I/O / parsing error occurs,
+    /// returns an error.
+    pub fn convert(&self) -> Result<()> {
+        let input_path = self
+            .input
+            .as_ref()
+            .ok_or_else(|| anyhow!("No JSON input path was set"))?;
+        let output_path = self
+            .output
+            .as_ref()
+            .ok_or_else(|| anyhow!("No CIFF output path was set"))?;
 
-        // 2) Write postings
-        eprintln!("Writing postings");
-        let progress = ProgressBar::new(terms.len() as u64);
-        progress.set_style(pb_style());
-        progress.set_draw_delta(10);
+        // Detect and parse the input documents up front and hold them all in memory, so the
+        // two passes below (quantization range, then postings) don't reopen or reparse the
+        // file.
+        let format = resolve_document_format(input_path, self.format)?;
+        eprintln!("Reading input documents as {format:?}");
+        let input_file =
+            File::open(input_path).with_context(|| format!("Cannot open {input_path:?}"))?;
+        let total_input_size = input_file.metadata()?.len();
+        let reader = BufReader::new(input_file);
+        let docs: Vec<JsonDoc> = match format {
+            DocumentFormat::Ndjson => read_ndjson_documents(reader, total_input_size)?,
+            DocumentFormat::JsonArray => read_json_array_documents(reader)?,
+            DocumentFormat::Csv => read_csv_documents(reader)?,
+            DocumentFormat::Auto => unreachable!("resolve_document_format never returns Auto"),
+        };
 
-        for (term, mut posting_pairs) in terms {
-            // Sort by docid
-            posting_pairs.sort_by_key(|(docid, _)| *docid);
+        write_ciff_from_documents(
+            docs,
+            output_path,
+            self.quantize,
+            self.quantize_bits,
+            self.quantization_scheme,
+            self.max_memory,
+            self.threads,
+            "Converted from JSON lines",
+        )
+    }
+}
 
-            let df = posting_pairs.len() as i64;
-            let cf = posting_pairs
-                .iter()
-                .map(|(_, tf)| i64::from(*tf))
-                .sum::<i64>();
+/// Assigns integer docids, quantizes and spills postings (in parallel via rayon, see
+/// [`PostingsSpiller`]), merges the resulting runs, and writes the CIFF file at `output_path`.
+/// Shared by [`JsonlToCiff::convert`] and [`ParquetToCiff::convert`], which differ only in how
+/// they parse their input into `docs`.
+fn write_ciff_from_documents(
+    docs: Vec<JsonDoc>,
+    output_path: &Path,
+    quantize: bool,
+    quantize_bits: u8,
+    quantization_scheme: QuantizationScheme,
+    max_memory: usize,
+    threads: usize,
+    description: &str,
+) -> Result<()> {
+    // If quantization is enabled, find the score range with a parallel min/max
+    // reduction over the already-parsed documents, instead of a second file read.
+    let (min_score, max_score) = if quantize {
+        eprintln!("Finding score range for quantization");
+        let (min_val, max_val) = docs
+            .par_iter()
+            .fold(
+                || (f64::INFINITY, f64::NEG_INFINITY),
+                |(min_val, max_val), jdoc| {
+                    jdoc.vector.values().fold((min_val, max_val), |(min_val, max_val), &score| {
+                        if score > 0.0 {
+                            (min_val.min(score), max_val.max(score))
+                        } else {
+                            (min_val, max_val)
+                        }
+                    })
+                },
+            )
+            .reduce(
+                || (f64::INFINITY, f64::NEG_INFINITY),
+                |(a_min, a_max), (b_min, b_max)| (a_min.min(b_min), a_max.max(b_max)),
+            );
 
-            let mut postings_list = PostingsList::new();
-            postings_list.set_term(term);
-            postings_list.set_df(df);
-            postings_list.set_cf(cf);
+        if min_val.is_infinite() || max_val.is_infinite() {
+            return Err(anyhow!("No valid scores found for quantization"));
+        }
 
-            let mut last_doc = 0;
-            for (docid, tf) in posting_pairs {
-                let mut posting = Posting::new();
-                posting.set_docid(docid - last_doc);
-                posting.set_tf(tf);
-                postings_list.postings.push(posting);
-                last_doc = docid;
+        eprintln!("Score range: {min_val} to {max_val}");
+        (min_val, max_val)
+    } else {
+        (0.0, 0.0) // Not used when quantize is false
+    };
+
+    // Map from a string "collection" docid to an internal integer docid. Assignment order
+    // must follow input order, so this stays a sequential pass; it's cheap relative to the
+    // per-term quantization and hashing work parallelized below.
+    let mut docid_map: HashMap<String, i32> = HashMap::new();
+    let mut current_docid: i32 = -1;
+    let ciff_docids: Vec<i32> = docs
+        .iter()
+        .map(|jdoc| match docid_map.get(&jdoc.id) {
+            Some(&docid) => docid,
+            None => {
+                current_docid += 1;
+                docid_map.insert(jdoc.id.clone(), current_docid);
+                current_docid
             }
-            coded_out.write_message_no_tag(&postings_list)?;
-            progress.inc(1);
+        })
+        .collect();
+    drop(docid_map);
+
+    // Spilling (term, docid, tf) postings to disk (see `PostingsSpiller`) and the score
+    // quantization below are the CPU-bound parts of conversion, so they run across a rayon
+    // thread pool: each fold partition gets its own spiller (sharing `spill_dir`, but
+    // disambiguated by `spiller_id`) and doc-record buffer, merged together below.
+    let spill_dir = TempDir::new()?;
+    let spiller_id = std::sync::atomic::AtomicUsize::new(0);
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .context("Failed to build rayon thread pool")?;
+
+    eprintln!("Processing documents");
+    let pb = ProgressBar::new(docs.len() as u64);
+    pb.set_style(pb_style());
+
+    type Partial = (PostingsSpiller, Vec<DocRecord>, HashSet<String>, i64);
+    let partials: Vec<Result<Partial>> = pool.install(|| {
+        docs.into_par_iter()
+            .zip(ciff_docids.into_par_iter())
+            .fold(
+                || -> Result<Partial> {
+                    let id = spiller_id.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    Ok((
+                        PostingsSpiller::new(spill_dir.path().to_path_buf(), id, max_memory),
+                        Vec::new(),
+                        HashSet::new(),
+                        0_i64,
+                    ))
+                },
+                |acc, (jdoc, ciff_docid)| {
+                    let (mut spiller, mut doc_records, mut distinct_terms, mut total_terms) =
+                        acc?;
+
+                    // Number of terms kept in this doc => doc_length
+                    let mut doc_length = 0_i64;
+                    for (term, score) in jdoc.vector {
+                        let tf = if quantize {
+                            // Scalar quantization: map [min_score, max_score] to
+                            // [1, 2^quantize_bits - 1]. We start at 1, not 0, to avoid
+                            // collisions with the zero values filtered out below.
+                            if score <= 0.0 {
+                                0 // Will be filtered out below
+                            } else {
+                                quantize_score(
+                                    score,
+                                    (min_score, max_score),
+                                    quantize_bits,
+                                    quantization_scheme,
+                                )
+                            }
+                        } else {
+                            // Assume scores are already pre-quantized integers
+                            score as i32
+                        };
+
+                        if tf <= 0 {
+                            continue; // skip zero or negative
+                        }
+                        doc_length += 1;
+
+                        distinct_terms.insert(term.clone());
+                        spiller.push(term, ciff_docid, tf)?;
+                    }
+                    total_terms += doc_length;
+
+                    // Build a DocRecord
+                    let mut record = DocRecord::new();
+                    record.set_docid(ciff_docid);
+                    record.set_collection_docid(jdoc.id);
+                    record.set_doclength(doc_length as i32);
+                    doc_records.push(record);
+
+                    pb.inc(1);
+                    Ok((spiller, doc_records, distinct_terms, total_terms))
+                },
+            )
+            .collect()
+    });
+    pb.finish();
+
+    let mut doc_records: Vec<DocRecord> = Vec::new();
+    let mut distinct_terms: HashSet<String> = HashSet::new();
+    let mut total_terms_in_collection: i64 = 0;
+    let mut run_paths: Vec<PathBuf> = Vec::new();
+    for partial in partials {
+        let (spiller, partial_records, partial_terms, partial_total) = partial?;
+        run_paths.extend(spiller.finish()?);
+        doc_records.extend(partial_records);
+        distinct_terms.extend(partial_terms);
+        total_terms_in_collection += partial_total;
+    }
+
+    // Sort doc_records by docid
+    doc_records.sort_by_key(DocRecord::get_docid);
+    let num_docs = doc_records.len() as i32;
+
+    let num_postings_lists = distinct_terms.len() as i32;
+    drop(distinct_terms);
+
+    // Build the CIFF Header
+    let mut header = proto::Header::default();
+    header.set_version(1);
+    header.set_num_postings_lists(num_postings_lists);
+    header.set_total_postings_lists(num_postings_lists);
+    header.set_num_docs(num_docs);
+    header.set_total_docs(num_docs);
+    header.set_total_terms_in_collection(total_terms_in_collection);
+    if num_docs > 0 {
+        header.set_average_doclength(total_terms_in_collection as f64 / f64::from(num_docs));
+    } else {
+        header.set_average_doclength(0.0);
+    }
+    header.set_description(if quantize {
+        format!(
+            "{description} (quantization: {quantization_scheme:?}, {quantize_bits}-bit)"
+        )
+    } else {
+        description.to_string()
+    });
+
+    // Open output CIFF file
+    let output_file = File::create(output_path)
+        .with_context(|| format!("Cannot create output file {output_path:?}"))?;
+    let mut writer = BufWriter::new(output_file);
+    let mut coded_out = CodedOutputStream::new(&mut writer);
+
+    // 1) Write header
+    coded_out.write_message_no_tag(&header)?;
+
+    // 2) Merge the spilled runs and write postings
+    eprintln!("Merging {} run(s) and writing postings", run_paths.len());
+    let progress = ProgressBar::new(num_postings_lists as u64);
+    progress.set_style(pb_style());
+    progress.set_draw_delta(10);
+
+    merge_runs(&run_paths, &mut coded_out, &progress)?;
+    progress.finish();
+
+    // 3) Write doc records
+    for record in doc_records {
+        coded_out.write_message_no_tag(&record)?;
+    }
+
+    coded_out.flush()?;
+    drop(coded_out);
+    writer.flush()?;
+
+    eprintln!(
+        "Wrote {num_docs} documents and {num_postings_lists} postings lists to {output_path:?}"
+    );
+
+    Ok(())
+}
+
+/// The sparse-vector column shapes [`ParquetToCiff`] recognizes, detected once from the file's
+/// schema and then reused for every record batch.
+enum ParquetVectorLayout {
+    /// A docid column plus parallel list columns: `term_ids`/`scores` (integer term ids) or
+    /// `terms`/`scores` (string terms), one list per row.
+    ParallelLists {
+        docid_column: String,
+        term_column: String,
+        terms_are_ids: bool,
+        score_column: String,
+    },
+    /// A docid column plus a single `Map<Utf8, Float64>` column of term to score.
+    MapColumn {
+        docid_column: String,
+        vector_column: String,
+    },
+}
+
+impl ParquetVectorLayout {
+    /// Inspects `schema` for one of the shapes documented on [`ParquetToCiff`], preferring
+    /// parallel list columns over a map column when a file happens to have both.
+    fn detect(schema: &Schema) -> Result<Self> {
+        let has_column = |name: &str| schema.field_with_name(name).is_ok();
+
+        let docid_column = ["docid", "id"]
+            .into_iter()
+            .find(|&name| has_column(name))
+            .ok_or_else(|| anyhow!("Parquet schema has no `docid` or `id` column"))?
+            .to_string();
+
+        if has_column("scores") && (has_column("term_ids") || has_column("terms")) {
+            let terms_are_ids = has_column("term_ids");
+            let term_column = if terms_are_ids { "term_ids" } else { "terms" }.to_string();
+            return Ok(Self::ParallelLists {
+                docid_column,
+                term_column,
+                terms_are_ids,
+                score_column: "scores".to_string(),
+            });
         }
-        progress.finish();
 
-        // 3) Write doc records
-        for record in doc_records {
-            coded_out.write_message_no_tag(&record)?;
+        if let Some(field) = schema
+            .fields()
+            .iter()
+            .find(|field| matches!(field.data_type(), DataType::Map(_, _)))
+        {
+            return Ok(Self::MapColumn {
+                docid_column,
+                vector_column: field.name().to_string(),
+            });
         }
 
-        coded_out.flush()?;
-        drop(coded_out);
-        writer.flush()?;
+        Err(anyhow!(
+            "Parquet schema must have `docid`/`id` plus either `term_ids`/`terms` and `scores` \
+             list columns, or a map column of term to score"
+        ))
+    }
 
-        eprintln!(
-            "Wrote {num_docs} documents and {num_postings_lists} postings lists to {output_path:?}"
-        );
+    /// Appends one [`JsonDoc`] per row of `batch` to `docs`.
+    fn read_batch_into(&self, batch: &RecordBatch, docs: &mut Vec<JsonDoc>) -> Result<()> {
+        match self {
+            Self::ParallelLists {
+                docid_column,
+                term_column,
+                terms_are_ids,
+                score_column,
+            } => {
+                let docids = docid_array(batch, docid_column)?;
+                let terms = list_column(batch, term_column)?;
+                let scores = list_column(batch, score_column)?;
+
+                for row in 0..batch.num_rows() {
+                    let score_values = scores
+                        .value(row)
+                        .as_any()
+                        .downcast_ref::<Float64Array>()
+                        .ok_or_else(|| anyhow!("`{score_column}` must be a list of float64"))?
+                        .clone();
+                    let term_values = terms.value(row);
+
+                    let mut vector = HashMap::new();
+                    if *terms_are_ids {
+                        let ids = term_values
+                            .as_any()
+                            .downcast_ref::<Int64Array>()
+                            .ok_or_else(|| anyhow!("`{term_column}` must be a list of int64"))?;
+                        for i in 0..ids.len() {
+                            vector.insert(ids.value(i).to_string(), score_values.value(i));
+                        }
+                    } else {
+                        let names = term_values
+                            .as_any()
+                            .downcast_ref::<StringArray>()
+                            .ok_or_else(|| anyhow!("`{term_column}` must be a list of strings"))?;
+                        for i in 0..names.len() {
+                            vector.insert(names.value(i).to_string(), score_values.value(i));
+                        }
+                    }
+
+                    docs.push(JsonDoc {
+                        id: docid_array_value(docids, row),
+                        _content: String::new(),
+                        vector,
+                    });
+                }
+            }
+            Self::MapColumn {
+                docid_column,
+                vector_column,
+            } => {
+                let docids = docid_array(batch, docid_column)?;
+                let maps = batch
+                    .column_by_name(vector_column)
+                    .ok_or_else(|| anyhow!("Parquet batch is missing `{vector_column}`"))?
+                    .as_any()
+                    .downcast_ref::<MapArray>()
+                    .ok_or_else(|| anyhow!("`{vector_column}` must be a map column"))?;
+
+                for row in 0..batch.num_rows() {
+                    let entries = maps.value(row);
+                    let names = entries
+                        .column(0)
+                        .as_any()
+                        .downcast_ref::<StringArray>()
+                        .ok_or_else(|| anyhow!("`{vector_column}` keys must be strings"))?;
+                    let scores = entries
+                        .column(1)
+                        .as_any()
+                        .downcast_ref::<Float64Array>()
+                        .ok_or_else(|| anyhow!("`{vector_column}` values must be float64"))?;
+
+                    let vector = (0..entries.num_rows())
+                        .map(|i| (names.value(i).to_string(), scores.value(i)))
+                        .collect();
+
+                    docs.push(JsonDoc {
+                        id: docid_array_value(docids, row),
+                        _content: String::new(),
+                        vector,
+                    });
+                }
+            }
+        }
 
         Ok(())
     }
 }
 
+/// An int64 or utf8 docid column, read generically as a string so it lines up with
+/// [`JsonDoc::id`] (and [`deserialize_id_to_string`]'s handling of the JSONL path).
+#[derive(Clone, Copy)]
+enum DocidArray<'a> {
+    Int64(&'a Int64Array),
+    Utf8(&'a StringArray),
+}
+
+fn docid_array<'a>(batch: &'a RecordBatch, column: &str) -> Result<DocidArray<'a>> {
+    let array = batch
+        .column_by_name(column)
+        .ok_or_else(|| anyhow!("Parquet batch is missing `{column}`"))?;
+    if let Some(ints) = array.as_any().downcast_ref::<Int64Array>() {
+        Ok(DocidArray::Int64(ints))
+    } else if let Some(strings) = array.as_any().downcast_ref::<StringArray>() {
+        Ok(DocidArray::Utf8(strings))
+    } else {
+        Err(anyhow!("`{column}` must be an int64 or utf8 column"))
+    }
+}
+
+fn docid_array_value(docids: DocidArray<'_>, row: usize) -> String {
+    match docids {
+        DocidArray::Int64(ints) => ints.value(row).to_string(),
+        DocidArray::Utf8(strings) => strings.value(row).to_string(),
+    }
+}
+
+fn list_column<'a>(batch: &'a RecordBatch, column: &str) -> Result<&'a ListArray> {
+    batch
+        .column_by_name(column)
+        .ok_or_else(|| anyhow!("Parquet batch is missing `{column}`"))?
+        .as_any()
+        .downcast_ref::<ListArray>()
+        .ok_or_else(|| anyhow!("`{column}` must be a list column"))
+}
+
+/// Converts sparse document vectors stored in a columnar Parquet file into a CIFF file, avoiding
+/// the JSON round-trip that large learned-sparse (e.g. SPLADE) exports would otherwise need. See
+/// [`JsonlToCiff`] for the JSON equivalent; both converters share the docid-assignment,
+/// quantization, and run-based merge logic in `write_ciff_from_documents`.
+///
+/// The input file's schema must provide a `docid` (or `id`) column, plus one of:
+///
+/// - parallel `term_ids`/`scores` or `terms`/`scores` list columns, one list per row;
+/// - a single `Map<Utf8, Float64>` column of term to score.
+#[derive(Debug, Clone)]
+pub struct ParquetToCiff {
+    input: Option<PathBuf>,
+    output: Option<PathBuf>,
+    quantize: bool,
+    quantize_bits: u8,
+    quantization_scheme: QuantizationScheme,
+    max_memory: usize,
+    threads: usize,
+}
+
+impl Default for ParquetToCiff {
+    fn default() -> Self {
+        Self {
+            input: None,
+            output: None,
+            quantize: false,
+            quantize_bits: 8,
+            quantization_scheme: QuantizationScheme::default(),
+            max_memory: DEFAULT_MAX_MEMORY_BYTES,
+            threads: 0,
+        }
+    }
+}
+
+impl ParquetToCiff {
+    /// Set the path of the Parquet file. Required.
+    pub fn input_path<P: Into<PathBuf>>(&mut self, path: P) -> &mut Self {
+        self.input = Some(path.into());
+        self
+    }
+
+    /// Set the output CIFF file path. Required.
+    pub fn output_path<P: Into<PathBuf>>(&mut self, path: P) -> &mut Self {
+        self.output = Some(path.into());
+        self
+    }
+
+    /// Set whether to quantize scores to integers. See [`JsonlToCiff::quantize`].
+    pub fn quantize(&mut self, quantize: bool) -> &mut Self {
+        self.quantize = quantize;
+        self
+    }
+
+    /// Set the bit width of quantized scores. See [`JsonlToCiff::quantize_bits`].
+    pub fn quantize_bits(&mut self, bits: u8) -> &mut Self {
+        self.quantize_bits = bits;
+        self
+    }
+
+    /// Set the scalar quantization scheme. See [`JsonlToCiff::quantization_scheme`].
+    pub fn quantization_scheme(&mut self, scheme: QuantizationScheme) -> &mut Self {
+        self.quantization_scheme = scheme;
+        self
+    }
+
+    /// Set the in-memory budget, in bytes, for buffered postings before they are spilled to a
+    /// run file on disk. See [`JsonlToCiff::max_memory`].
+    pub fn max_memory(&mut self, max_memory: usize) -> &mut Self {
+        self.max_memory = max_memory;
+        self
+    }
+
+    /// Set the number of threads used to quantize scores and build postings in parallel.
+    /// See [`JsonlToCiff::threads`].
+    pub fn threads(&mut self, threads: usize) -> &mut Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Performs the conversion from Parquet to CIFF.
+    ///
+    /// # Errors
+    ///
+    /// If the input / output is invalid, the schema doesn't match one of the supported shapes,
+    /// or any I/O / parsing error occurs, returns an error.
+    pub fn convert(&self) -> Result<()> {
+        let input_path = self
+            .input
+            .as_ref()
+            .ok_or_else(|| anyhow!("No Parquet input path was set"))?;
+        let output_path = self
+            .output
+            .as_ref()
+            .ok_or_else(|| anyhow!("No CIFF output path was set"))?;
+
+        eprintln!("Reading Parquet schema");
+        let input_file =
+            File::open(input_path).with_context(|| format!("Cannot open {input_path:?}"))?;
+        let builder = ParquetRecordBatchReaderBuilder::try_new(input_file)
+            .with_context(|| format!("Cannot read Parquet metadata from {input_path:?}"))?;
+        let layout = ParquetVectorLayout::detect(builder.schema())?;
+        let reader = builder
+            .build()
+            .with_context(|| format!("Cannot build Parquet reader for {input_path:?}"))?;
+
+        eprintln!("Reading document vectors");
+        let mut docs = Vec::new();
+        for batch in reader {
+            let batch = batch.context("Failed to read a Parquet record batch")?;
+            layout.read_batch_into(&batch, &mut docs)?;
+        }
+
+        write_ciff_from_documents(
+            docs,
+            output_path,
+            self.quantize,
+            self.quantize_bits,
+            self.quantization_scheme,
+            self.max_memory,
+            self.threads,
+            "Converted from Parquet",
+        )
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -1185,4 +3105,331 @@ mod test {
         assert!(Header::from_stream(&mut input).is_err());
         Ok(())
     }
+
+    #[test]
+    fn test_ciff_records_streams_in_order() -> Result<()> {
+        let mut header = proto::Header::default();
+        header.set_num_docs(2);
+        header.set_num_postings_lists(1);
+
+        let mut postings_list = PostingsList::default();
+        postings_list.set_term("a".to_string());
+        postings_list.set_df(1);
+
+        let mut doc_record = DocRecord::default();
+        doc_record.set_docid(0);
+        let mut other_doc_record = DocRecord::default();
+        other_doc_record.set_docid(1);
+
+        let mut buffer = Vec::<u8>::new();
+        let mut out = CodedOutputStream::vec(&mut buffer);
+        out.write_message_no_tag(&header)?;
+        out.write_message_no_tag(&postings_list)?;
+        out.write_message_no_tag(&doc_record)?;
+        out.write_message_no_tag(&other_doc_record)?;
+        out.flush()?;
+
+        let mut input = CodedInputStream::from_bytes(&buffer);
+        let mut records = CiffRecords::new(&mut input)?;
+        assert_eq!(records.header().num_documents, 2);
+        assert_eq!(records.header().num_postings_lists, 1);
+
+        let postings_lists: Vec<_> = records.postings_lists().collect::<Result<_>>()?;
+        assert_eq!(postings_lists, vec![postings_list]);
+
+        let doc_records: Vec<_> = records.doc_records().collect::<Result<_>>()?;
+        assert_eq!(doc_records, vec![doc_record, other_doc_record]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ciff_to_jsonl_and_back() -> Result<()> {
+        let temp = TempDir::new()?;
+        let ciff_path = temp.path().join("index.ciff");
+
+        let mut header = proto::Header::default();
+        header.set_version(1);
+        header.set_num_docs(2);
+        header.set_total_docs(2);
+        header.set_num_postings_lists(2);
+        header.set_total_postings_lists(2);
+
+        let mut term_a = PostingsList::default();
+        term_a.set_term("a".to_string());
+        term_a.set_df(2);
+        term_a.set_cf(3);
+        let mut posting = Posting::default();
+        posting.set_docid(0);
+        posting.set_tf(1);
+        term_a.postings.push(posting);
+        let mut posting = Posting::default();
+        posting.set_docid(1);
+        posting.set_tf(2);
+        term_a.postings.push(posting);
+
+        let mut term_b = PostingsList::default();
+        term_b.set_term("b".to_string());
+        term_b.set_df(1);
+        term_b.set_cf(5);
+        let mut posting = Posting::default();
+        posting.set_docid(0);
+        posting.set_tf(5);
+        term_b.postings.push(posting);
+
+        let mut doc0 = DocRecord::default();
+        doc0.set_docid(0);
+        doc0.set_collection_docid("doc0".to_string());
+        doc0.set_doclength(2);
+        let mut doc1 = DocRecord::default();
+        doc1.set_docid(1);
+        doc1.set_collection_docid("doc1".to_string());
+        doc1.set_doclength(1);
+
+        let mut writer = BufWriter::new(File::create(&ciff_path)?);
+        let mut out = CodedOutputStream::new(&mut writer);
+        out.write_message_no_tag(&header)?;
+        out.write_message_no_tag(&term_a)?;
+        out.write_message_no_tag(&term_b)?;
+        out.write_message_no_tag(&doc0)?;
+        out.write_message_no_tag(&doc1)?;
+        out.flush()?;
+        drop(out);
+        writer.flush()?;
+
+        let jsonl_path = temp.path().join("index.jsonl");
+        CiffToJsonl::default()
+            .input_path(&ciff_path)
+            .output_path(&jsonl_path)
+            .convert()?;
+
+        let rebuilt_ciff_path = temp.path().join("rebuilt.ciff");
+        JsonlToCiff::default()
+            .input_path(&jsonl_path)
+            .output_path(&rebuilt_ciff_path)
+            .convert()?;
+
+        let jsonl_path_2 = temp.path().join("index2.jsonl");
+        CiffToJsonl::default()
+            .input_path(&rebuilt_ciff_path)
+            .output_path(&jsonl_path_2)
+            .convert()?;
+
+        assert_eq!(
+            std::fs::read_to_string(&jsonl_path)?,
+            std::fs::read_to_string(&jsonl_path_2)?
+        );
+        Ok(())
+    }
+
+    fn write_shard(path: &Path, docs: &[(&str, Vec<(&str, i32)>)]) -> Result<()> {
+        let mut terms: BTreeMap<&str, Vec<(i32, i32)>> = BTreeMap::new();
+        for (docid, (_, vector)) in docs.iter().enumerate() {
+            for (term, tf) in vector {
+                terms.entry(term).or_default().push((docid as i32, *tf));
+            }
+        }
+
+        let mut header = proto::Header::default();
+        header.set_version(1);
+        header.set_num_docs(docs.len() as i32);
+        header.set_total_docs(docs.len() as i32);
+        header.set_num_postings_lists(terms.len() as i32);
+        header.set_total_postings_lists(terms.len() as i32);
+        let total_terms: i64 = docs.iter().map(|(_, vector)| vector.len() as i64).sum();
+        header.set_total_terms_in_collection(total_terms);
+        header.set_average_doclength(if docs.is_empty() {
+            0.0
+        } else {
+            total_terms as f64 / docs.len() as f64
+        });
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        let mut out = CodedOutputStream::new(&mut writer);
+        out.write_message_no_tag(&header)?;
+        for (term, postings) in terms {
+            let mut posting_list = PostingsList::default();
+            posting_list.set_term(term.to_string());
+            posting_list.set_df(postings.len() as i64);
+            posting_list.set_cf(postings.iter().map(|(_, tf)| i64::from(*tf)).sum());
+            let mut last_doc = 0;
+            for (docid, tf) in postings {
+                let mut posting = Posting::default();
+                posting.set_docid(docid - last_doc);
+                posting.set_tf(tf);
+                posting_list.postings.push(posting);
+                last_doc = docid;
+            }
+            out.write_message_no_tag(&posting_list)?;
+        }
+        for (docid, (title, vector)) in docs.iter().enumerate() {
+            let mut record = DocRecord::default();
+            record.set_docid(docid as i32);
+            record.set_collection_docid((*title).to_string());
+            record.set_doclength(vector.len() as i32);
+            out.write_message_no_tag(&record)?;
+        }
+        out.flush()?;
+        drop(out);
+        writer.flush()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_ciff() -> Result<()> {
+        let temp = TempDir::new()?;
+        let shard0 = temp.path().join("shard0.ciff");
+        let shard1 = temp.path().join("shard1.ciff");
+        write_shard(&shard0, &[("doc0", vec![("a", 1), ("b", 2)])])?;
+        write_shard(&shard1, &[("doc1", vec![("a", 3)])])?;
+
+        let merged = temp.path().join("merged.ciff");
+        MergeCiff::default()
+            .input_paths([&shard0, &shard1])
+            .output_path(&merged)
+            .merge()?;
+
+        let jsonl = temp.path().join("merged.jsonl");
+        CiffToJsonl::default()
+            .input_path(&merged)
+            .output_path(&jsonl)
+            .convert()?;
+
+        assert_eq!(
+            std::fs::read_to_string(&jsonl)?,
+            "{\"id\":\"doc0\",\"vector\":{\"a\":1.0,\"b\":2.0}}\n{\"id\":\"doc1\",\"vector\":{\"a\":3.0}}\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_ciff_rejects_duplicate_titles() -> Result<()> {
+        let temp = TempDir::new()?;
+        let shard0 = temp.path().join("shard0.ciff");
+        let shard1 = temp.path().join("shard1.ciff");
+        write_shard(&shard0, &[("doc0", vec![("a", 1)])])?;
+        write_shard(&shard1, &[("doc0", vec![("a", 1)])])?;
+
+        let merged = temp.path().join("merged.ciff");
+        assert!(MergeCiff::default()
+            .input_paths([&shard0, &shard1])
+            .output_path(&merged)
+            .merge()
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_accepts_well_formed_ciff() -> Result<()> {
+        let temp = TempDir::new()?;
+        let ciff_path = temp.path().join("index.ciff");
+        write_shard(
+            &ciff_path,
+            &[("doc0", vec![("a", 1), ("b", 2)]), ("doc1", vec![("a", 3)])],
+        )?;
+
+        let report = CiffValidator::default().input_path(&ciff_path).validate()?;
+        assert!(report.is_valid(), "{report}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_reports_df_mismatch_and_bad_doc_length() -> Result<()> {
+        let temp = TempDir::new()?;
+        let ciff_path = temp.path().join("index.ciff");
+
+        let mut header = proto::Header::default();
+        header.set_version(1);
+        header.set_num_docs(1);
+        header.set_total_docs(1);
+        header.set_num_postings_lists(1);
+        header.set_total_postings_lists(1);
+
+        let mut posting_list = PostingsList::default();
+        posting_list.set_term("a".to_string());
+        posting_list.set_df(5); // does not match the single posting below
+        posting_list.set_cf(5);
+        let mut posting = Posting::default();
+        posting.set_docid(0);
+        posting.set_tf(1);
+        posting_list.postings.push(posting);
+
+        let mut doc_record = DocRecord::default();
+        doc_record.set_docid(0);
+        doc_record.set_collection_docid("doc0".to_string());
+        doc_record.set_doclength(0); // non-positive length
+
+        let mut writer = BufWriter::new(File::create(&ciff_path)?);
+        let mut out = CodedOutputStream::new(&mut writer);
+        out.write_message_no_tag(&header)?;
+        out.write_message_no_tag(&posting_list)?;
+        out.write_message_no_tag(&doc_record)?;
+        out.flush()?;
+        drop(out);
+        writer.flush()?;
+
+        let report = CiffValidator::default().input_path(&ciff_path).validate()?;
+        assert!(!report.is_valid());
+        assert!(report
+            .issues()
+            .iter()
+            .any(|issue| issue.to_string().contains("declares df=5 but has 1")));
+        assert!(report
+            .issues()
+            .iter()
+            .any(|issue| issue.to_string().contains("non-positive length")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_reports_average_doclength_mismatch() -> Result<()> {
+        let temp = TempDir::new()?;
+        let ciff_path = temp.path().join("index.ciff");
+
+        let mut header = proto::Header::default();
+        header.set_version(1);
+        header.set_num_docs(2);
+        header.set_total_docs(2);
+        header.set_num_postings_lists(1);
+        header.set_total_postings_lists(1);
+        header.set_average_doclength(10.0); // actual average below is 5.0
+
+        let mut posting_list = PostingsList::default();
+        posting_list.set_term("a".to_string());
+        posting_list.set_df(1);
+        posting_list.set_cf(1);
+        let mut posting = Posting::default();
+        posting.set_docid(0);
+        posting.set_tf(1);
+        posting_list.postings.push(posting);
+
+        let mut doc_record0 = DocRecord::default();
+        doc_record0.set_docid(0);
+        doc_record0.set_collection_docid("doc0".to_string());
+        doc_record0.set_doclength(4);
+
+        let mut doc_record1 = DocRecord::default();
+        doc_record1.set_docid(1);
+        doc_record1.set_collection_docid("doc1".to_string());
+        doc_record1.set_doclength(6);
+
+        let mut writer = BufWriter::new(File::create(&ciff_path)?);
+        let mut out = CodedOutputStream::new(&mut writer);
+        out.write_message_no_tag(&header)?;
+        out.write_message_no_tag(&posting_list)?;
+        out.write_message_no_tag(&doc_record0)?;
+        out.write_message_no_tag(&doc_record1)?;
+        out.flush()?;
+        drop(out);
+        writer.flush()?;
+
+        let report = CiffValidator::default().input_path(&ciff_path).validate()?;
+        assert!(!report.is_valid());
+        assert!(report
+            .issues()
+            .iter()
+            .any(|issue| issue.to_string().contains("average_doclength=10")
+                && issue.to_string().contains("average 5")));
+        Ok(())
+    }
 }
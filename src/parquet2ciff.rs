@@ -0,0 +1,52 @@
+mod proto;
+pub use proto::{DocRecord, Header, Posting, PostingsList};
+
+use ciff::{ParquetToCiff, QuantizationScheme};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "parquet2ciff",
+    about = "Convert a columnar Parquet file of sparse document vectors into the Common Index Format [v1]"
+)]
+struct Args {
+    #[structopt(short, long, help = "Path to Parquet file")]
+    input: PathBuf,
+    #[structopt(short, long, help = "Output CIFF file")]
+    output: PathBuf,
+    #[structopt(short, long, help = "Quantize scores to integers")]
+    quantize: bool,
+    #[structopt(long, help = "Bit width of quantized scores", default_value = "8")]
+    quantize_bits: u8,
+    #[structopt(
+        long,
+        help = "Quantization scheme: linear or log",
+        default_value = "linear"
+    )]
+    quantization_scheme: QuantizationScheme,
+    #[structopt(
+        long,
+        help = "Number of threads to use, or 0 to let rayon pick",
+        default_value = "0"
+    )]
+    threads: usize,
+}
+
+fn main() {
+    let args = Args::from_args();
+
+    let mut converter = ParquetToCiff::default();
+    converter
+        .input_path(args.input)
+        .output_path(args.output)
+        .quantize(args.quantize)
+        .quantize_bits(args.quantize_bits)
+        .quantization_scheme(args.quantization_scheme)
+        .threads(args.threads);
+
+    if let Err(error) = converter.convert() {
+        eprintln!("ERROR: {error}");
+        std::process::exit(1);
+    }
+}
@@ -1,9 +1,72 @@
+use lru::LruCache;
+use std::cell::RefCell;
 use std::convert::TryInto;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::num::NonZeroUsize;
 use std::ops::{Deref, Index};
 use std::path::Path;
 
+/// One-byte marker at the start of a [`PayloadVector`] written with an explicit [`OffsetWidth`],
+/// distinguishing it from the legacy layout (always `u64` offsets, no marker). See
+/// [`PayloadSlice::format`] for how a reader tells the two apart.
+const TAGGED_FORMAT_MARKER: u8 = 0xff;
+
+/// Byte width of the offsets in a [`PayloadVector`]'s offset table, chosen to be just wide
+/// enough to address the vector's total payload size. Narrower widths shrink the
+/// `(len + 1) * width` offset table that precedes the payloads, which for a lexicon of many
+/// short terms often dwarfs the payloads themselves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OffsetWidth {
+    /// 2-byte offsets, addressing payload regions up to `u16::MAX` bytes.
+    U16,
+    /// 4-byte offsets, addressing payload regions up to `u32::MAX` bytes.
+    U32,
+    /// 8-byte offsets, addressing any payload region representable in memory. Also the layout
+    /// used by untagged, legacy `PayloadVector` output.
+    U64,
+}
+
+impl OffsetWidth {
+    /// Picks the narrowest width whose offsets can address a payload region of
+    /// `total_payload_bytes` bytes.
+    #[must_use]
+    fn narrowest_for(total_payload_bytes: u64) -> Self {
+        if total_payload_bytes <= u64::from(u16::MAX) {
+            OffsetWidth::U16
+        } else if total_payload_bytes <= u64::from(u32::MAX) {
+            OffsetWidth::U32
+        } else {
+            OffsetWidth::U64
+        }
+    }
+
+    fn byte_width(self) -> usize {
+        match self {
+            OffsetWidth::U16 => 2,
+            OffsetWidth::U32 => 4,
+            OffsetWidth::U64 => 8,
+        }
+    }
+
+    fn tag(self) -> u8 {
+        match self {
+            OffsetWidth::U16 => 0,
+            OffsetWidth::U32 => 1,
+            OffsetWidth::U64 => 2,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(OffsetWidth::U16),
+            1 => Some(OffsetWidth::U32),
+            2 => Some(OffsetWidth::U64),
+            _ => None,
+        }
+    }
+}
+
 /// Owning variant of [`PayloadSlice`], in which the underlying bytes are fully
 /// in memory within the struct. This is useful mainly for building the structure
 /// before writing it to a file, but also if one decides to fully load the bytes
@@ -29,35 +92,85 @@ impl AsRef<PayloadSlice> for PayloadVector {
     }
 }
 
-impl<Item> std::iter::FromIterator<Item> for PayloadVector
-where
-    Item: AsRef<[u8]>,
-{
-    fn from_iter<T: IntoIterator<Item = Item>>(iter: T) -> Self {
-        let mut data = Vec::<u8>::new();
-        let mut length: u64 = 0;
+impl PayloadVector {
+    /// Builds a payload vector, auto-selecting the narrowest [`OffsetWidth`] that can address
+    /// the total payload size. This is what the [`FromIterator`](std::iter::FromIterator) impl
+    /// uses; call [`Self::with_offset_width`] instead to force a specific width.
+    #[must_use]
+    pub fn with_auto_offset_width<Item, T>(iter: T) -> Self
+    where
+        Item: AsRef<[u8]>,
+        T: IntoIterator<Item = Item>,
+    {
+        let items: Vec<Item> = iter.into_iter().collect();
+        let total_payload_bytes: u64 = items.iter().map(|item| item.as_ref().len() as u64).sum();
+        Self::build(items, OffsetWidth::narrowest_for(total_payload_bytes))
+    }
 
-        // Write empty bytes that will be modified once length is known.
+    /// Builds a payload vector with a forced offset `width`, for reproducibility or to avoid
+    /// re-measuring the payload size `with_auto_offset_width` performs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the total payload size does not fit in `width`.
+    #[must_use]
+    pub fn with_offset_width<Item, T>(iter: T, width: OffsetWidth) -> Self
+    where
+        Item: AsRef<[u8]>,
+        T: IntoIterator<Item = Item>,
+    {
+        Self::build(iter.into_iter().collect(), width)
+    }
+
+    fn build<Item: AsRef<[u8]>>(items: Vec<Item>, width: OffsetWidth) -> Self {
+        let length = items.len() as u64;
+
+        let mut data = Vec::<u8>::new();
+        data.push(TAGGED_FORMAT_MARKER);
+        data.push(width.tag());
         data.extend(length.to_le_bytes());
 
         // Must collect separately, to later append to `data`.
         let mut payloads = Vec::<u8>::new();
         let mut offset: u64 = 0;
 
-        data.extend(offset.to_le_bytes());
-        for item in iter {
+        Self::push_offset(&mut data, offset, width);
+        for item in &items {
             let bytes: &[u8] = item.as_ref();
             payloads.extend(bytes);
             offset += bytes.len() as u64;
-            length += 1;
-            data.extend(offset.to_le_bytes());
+            Self::push_offset(&mut data, offset, width);
         }
 
         data.extend(payloads);
-        data[..std::mem::size_of::<u64>()].copy_from_slice(&length.to_le_bytes());
 
         Self { data }
     }
+
+    fn push_offset(data: &mut Vec<u8>, offset: u64, width: OffsetWidth) {
+        match width {
+            OffsetWidth::U16 => data.extend(
+                u16::try_from(offset)
+                    .expect("payload offset does not fit in the forced u16 width")
+                    .to_le_bytes(),
+            ),
+            OffsetWidth::U32 => data.extend(
+                u32::try_from(offset)
+                    .expect("payload offset does not fit in the forced u32 width")
+                    .to_le_bytes(),
+            ),
+            OffsetWidth::U64 => data.extend(offset.to_le_bytes()),
+        }
+    }
+}
+
+impl<Item> std::iter::FromIterator<Item> for PayloadVector
+where
+    Item: AsRef<[u8]>,
+{
+    fn from_iter<T: IntoIterator<Item = Item>>(iter: T) -> Self {
+        Self::with_auto_offset_width(iter)
+    }
 }
 
 impl<'a> Deref for PayloadVector {
@@ -197,10 +310,13 @@ impl PayloadSlice {
         if index >= self.len() {
             None
         } else {
-            let payloads_offset = (self.len() as usize + 2) * 8;
-            let offset_pos = (index as usize + 1) * 8;
-            let offset = payloads_offset + self.int_at(offset_pos) as usize;
-            let next_offset = payloads_offset + self.int_at(offset_pos + 8) as usize;
+            let (header_pos, width) = self.format();
+            let w = width.byte_width();
+            let offsets_pos = header_pos + 8;
+            let payloads_offset = offsets_pos + (self.len() as usize + 1) * w;
+            let offset_pos = offsets_pos + index as usize * w;
+            let offset = payloads_offset + self.offset_at(width, offset_pos) as usize;
+            let next_offset = payloads_offset + self.offset_at(width, offset_pos + w) as usize;
             self.data.get(offset..next_offset)
         }
     }
@@ -208,7 +324,8 @@ impl PayloadSlice {
     /// Returns the length of the slice.
     #[must_use]
     pub fn len(&self) -> u64 {
-        self.int_at(0)
+        let (header_pos, _) = self.format();
+        self.int_at(header_pos)
     }
 
     /// Checks if the slice is empty.
@@ -226,6 +343,100 @@ impl PayloadSlice {
         }
     }
 
+    /// Binary-searches for `key` among the elements, using `compare` in place of byte-lexical
+    /// comparison. `self` must already be sorted according to `compare`, as
+    /// [`build_lexicon`]'s input is required to be; this is the caller's responsibility and is
+    /// not itself checked.
+    ///
+    /// Returns the index of a matching element, or `Err(insertion_point)` if none matches, where
+    /// `insertion_point` is the index `key` would need to be inserted at to keep `self` sorted.
+    /// Runs in `O(log N)` [`Self::get`] calls, so a memmap-backed slice only touches a
+    /// logarithmic number of pages rather than scanning the whole file.
+    pub fn binary_search_by<F>(&self, mut compare: F) -> Result<u64, u64>
+    where
+        F: FnMut(&[u8]) -> std::cmp::Ordering,
+    {
+        let mut low = 0_u64;
+        let mut high = self.len();
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match compare(self.get(mid).expect("mid is within bounds")) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => return Ok(mid),
+            }
+        }
+        Err(low)
+    }
+
+    /// Returns the index of `key`, or `None` if it is not present. A thin wrapper over
+    /// [`Self::binary_search_by`] that compares `key` byte-lexicographically, so `self` must be
+    /// sorted in that order; [`build_lexicon`] keeps its input's order and does not sort it.
+    #[must_use]
+    pub fn position(&self, key: &[u8]) -> Option<u64> {
+        self.binary_search_by(|element| element.cmp(key)).ok()
+    }
+
+    /// Checks whether `key` is present.
+    #[must_use]
+    pub fn contains(&self, key: &[u8]) -> bool {
+        self.position(key).is_some()
+    }
+
+    /// Determines the byte position of the length field and the width of the offset table that
+    /// follows it, by checking for the [`OffsetWidth`] tag written by
+    /// [`PayloadVector::with_offset_width`]/[`PayloadVector::with_auto_offset_width`].
+    ///
+    /// A tag is only trusted if the resulting layout is internally consistent (its last offset
+    /// exactly accounts for the remaining bytes as payload); this is what lets untagged, legacy
+    /// `PayloadVector` output (always `u64` offsets starting at byte `0`) keep reading correctly
+    /// even though its first bytes could, in principle, coincidentally look like a tag.
+    fn format(&self) -> (usize, OffsetWidth) {
+        if self.data.first() == Some(&TAGGED_FORMAT_MARKER) {
+            if let Some(width) = self.data.get(1).copied().and_then(OffsetWidth::from_tag) {
+                if self.format_is_consistent(2, width) {
+                    return (2, width);
+                }
+            }
+        }
+        (0, OffsetWidth::U64)
+    }
+
+    /// Checks that the offset table at `header_pos` (see [`Self::format`]) is consistent with
+    /// the total size of the underlying data: specifically, that the table fits, and that its
+    /// last entry accounts for exactly the bytes remaining after it.
+    fn format_is_consistent(&self, header_pos: usize, width: OffsetWidth) -> bool {
+        let w = width.byte_width();
+        if self.data.len() < header_pos + 8 {
+            return false;
+        }
+        let length = self.int_at(header_pos) as usize;
+        let offsets_pos = header_pos + 8;
+        let Some(table_bytes) = (length + 1).checked_mul(w) else {
+            return false;
+        };
+        let Some(payloads_offset) = offsets_pos.checked_add(table_bytes) else {
+            return false;
+        };
+        if payloads_offset > self.data.len() {
+            return false;
+        }
+        let last_offset = self.offset_at(width, offsets_pos + length * w);
+        last_offset as usize == self.data.len() - payloads_offset
+    }
+
+    fn offset_at(&self, width: OffsetWidth, offset: usize) -> u64 {
+        match width {
+            OffsetWidth::U16 => {
+                u64::from(u16::from_le_bytes(self.data[offset..offset + 2].try_into().unwrap()))
+            }
+            OffsetWidth::U32 => {
+                u64::from(u32::from_le_bytes(self.data[offset..offset + 4].try_into().unwrap()))
+            }
+            OffsetWidth::U64 => self.int_at(offset),
+        }
+    }
+
     fn int_at(&self, offset: usize) -> u64 {
         u64::from_le_bytes(self.data[offset..offset + 8].try_into().unwrap())
     }
@@ -260,6 +471,443 @@ pub fn build_lexicon(input: &Path, output: &Path) -> io::Result<()> {
     Ok(())
 }
 
+/// Default bucket size (`k`) used by [`build_front_coded_lexicon`] and
+/// [`FrontCodedLexicon::from_sorted_iter`].
+const DEFAULT_FRONT_CODING_BUCKET_SIZE: u64 = 16;
+
+/// Alternative to [`build_lexicon`] that front-codes the sorted terms in `input` (see
+/// [`FrontCodedLexicon`]) before writing them to `output`, trading lookup simplicity for a
+/// substantially smaller lexicon file when most adjacent terms share long prefixes.
+///
+/// # Errors
+///
+/// Will return an error if `input` cannot be read or `output` cannot be written.
+pub fn build_front_coded_lexicon(input: &Path, output: &Path, bucket_size: u64) -> io::Result<()> {
+    let terms = BufReader::new(File::open(input)?)
+        .lines()
+        .collect::<Result<Vec<String>, _>>()?;
+    let lex = FrontCodedLexicon::from_sorted_iter(terms, bucket_size);
+    let mut lex_path = BufWriter::new(File::create(output)?);
+    lex.write(&mut lex_path)?;
+    Ok(())
+}
+
+fn write_varint<W: Write>(writer: &mut W, mut value: u64) -> io::Result<()> {
+    loop {
+        let low = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            writer.write_all(&[low])?;
+            return Ok(());
+        }
+        writer.write_all(&[low | 0x80])?;
+    }
+}
+
+/// Decodes a single varint-encoded value from the front of `bytes`, returning the decoded value
+/// together with the number of bytes it occupied. Panics on a truncated varint, since this is
+/// only ever used to read back a [`FrontCodedLexicon`]'s own output.
+fn read_varint(bytes: &[u8]) -> (u64, usize) {
+    let mut value: u64 = 0;
+    let mut shift = 0;
+    for (i, &byte) in bytes.iter().enumerate() {
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return (value, i + 1);
+        }
+        shift += 7;
+    }
+    panic!("truncated varint in front-coded lexicon")
+}
+
+/// Owning, front-coded variant of a sorted term dictionary: an alternative to [`PayloadVector`]
+/// for the output of [`build_lexicon`] that exploits the fact that adjacent terms in a sorted
+/// term list typically share long prefixes. See [`FrontCodedLexiconSlice`] for the read-only,
+/// memmap-friendly view over the bytes this builds.
+///
+/// Terms are grouped into buckets of `bucket_size` (`k`): the first term in each bucket (the
+/// "restart") is stored in full; each subsequent term in the bucket is stored relative to the
+/// previous term as a varint `shared_prefix_len`, a varint `suffix_len`, and the suffix bytes. A
+/// `u64` offset table points at each bucket's start, so [`FrontCodedLexiconSlice::get`] can seek
+/// directly to the bucket an index falls into rather than replaying from the start of the whole
+/// dictionary.
+#[derive(Debug, Clone)]
+pub struct FrontCodedLexicon {
+    data: Vec<u8>,
+}
+
+impl FrontCodedLexicon {
+    /// Builds a front-coded lexicon from `terms`, grouping them into buckets of `bucket_size`.
+    ///
+    /// `terms` must already be sorted byte-lexicographically, the same order [`build_lexicon`]
+    /// expects its input in; this is the caller's responsibility and is not itself checked.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bucket_size` is zero.
+    #[must_use]
+    pub fn from_sorted_iter<Item, T>(terms: T, bucket_size: u64) -> Self
+    where
+        Item: AsRef<[u8]>,
+        T: IntoIterator<Item = Item>,
+    {
+        assert!(bucket_size > 0, "bucket_size must be greater than zero");
+
+        let mut num_items: u64 = 0;
+        let mut bucket_offsets = Vec::<u64>::new();
+        let mut body = Vec::<u8>::new();
+        let mut previous = Vec::<u8>::new();
+
+        for term in terms {
+            let term = term.as_ref();
+            if num_items % bucket_size == 0 {
+                bucket_offsets.push(body.len() as u64);
+                write_varint(&mut body, term.len() as u64).unwrap();
+                body.extend_from_slice(term);
+            } else {
+                let shared_prefix_len = previous
+                    .iter()
+                    .zip(term)
+                    .take_while(|(a, b)| a == *b)
+                    .count();
+                let suffix = &term[shared_prefix_len..];
+                write_varint(&mut body, shared_prefix_len as u64).unwrap();
+                write_varint(&mut body, suffix.len() as u64).unwrap();
+                body.extend_from_slice(suffix);
+            }
+            previous.clear();
+            previous.extend_from_slice(term);
+            num_items += 1;
+        }
+
+        let num_buckets = bucket_offsets.len() as u64;
+
+        let mut data = Vec::<u8>::new();
+        data.extend(num_items.to_le_bytes());
+        data.extend(bucket_size.to_le_bytes());
+        data.extend(num_buckets.to_le_bytes());
+        for offset in &bucket_offsets {
+            data.extend(offset.to_le_bytes());
+        }
+        data.extend(body);
+
+        Self { data }
+    }
+
+    /// Borrows this lexicon as a [`FrontCodedLexiconSlice`].
+    #[must_use]
+    pub fn as_slice(&self) -> FrontCodedLexiconSlice<'_> {
+        FrontCodedLexiconSlice::new(&self.data)
+    }
+
+    /// Writes the underlying memory to the output.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if an error occurs while writing to the output.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.data)?;
+        writer.flush()
+    }
+}
+
+impl<Item> std::iter::FromIterator<Item> for FrontCodedLexicon
+where
+    Item: AsRef<[u8]>,
+{
+    /// Equivalent to [`FrontCodedLexicon::from_sorted_iter`] with
+    /// [`DEFAULT_FRONT_CODING_BUCKET_SIZE`]. `iter` must already be sorted; see
+    /// [`from_sorted_iter`](Self::from_sorted_iter).
+    fn from_iter<T: IntoIterator<Item = Item>>(iter: T) -> Self {
+        Self::from_sorted_iter(iter, DEFAULT_FRONT_CODING_BUCKET_SIZE)
+    }
+}
+
+/// Read-only, memmap-friendly view over bytes produced by [`FrontCodedLexicon`].
+///
+/// Like [`CompressedPayloadSlice::get`], [`FrontCodedLexiconSlice::get`] returns an owned
+/// `Vec<u8>` rather than a borrowed `&[u8]`, since a lookup reconstructs the term by replaying
+/// prefix-coded entries into a fresh buffer rather than pointing at one contiguous span.
+pub struct FrontCodedLexiconSlice<'a> {
+    data: &'a [u8],
+    num_items: u64,
+    bucket_size: u64,
+    bucket_offsets_pos: usize,
+    body_pos: usize,
+}
+
+impl<'a> FrontCodedLexiconSlice<'a> {
+    /// Wraps `data`, interpreting it as bytes written by [`FrontCodedLexicon::write`].
+    #[must_use]
+    pub fn new(data: &'a [u8]) -> Self {
+        let num_items = u64::from_le_bytes(data[0..8].try_into().unwrap());
+        let bucket_size = u64::from_le_bytes(data[8..16].try_into().unwrap());
+        let num_buckets = u64::from_le_bytes(data[16..24].try_into().unwrap());
+        let bucket_offsets_pos = 24;
+        let body_pos = bucket_offsets_pos + num_buckets as usize * 8;
+        Self {
+            data,
+            num_items,
+            bucket_size,
+            bucket_offsets_pos,
+            body_pos,
+        }
+    }
+
+    /// Returns the number of items.
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.num_items
+    }
+
+    /// Checks if the lexicon is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.num_items == 0
+    }
+
+    /// Returns the term at position `index`, or `None` if `index` is out of bounds. Seeks to the
+    /// bucket `index` falls into, then replays its `(prefix_len, suffix)` entries forward from
+    /// the bucket's restart term until `index` is reconstructed.
+    #[must_use]
+    pub fn get(&self, index: u64) -> Option<Vec<u8>> {
+        if index >= self.num_items {
+            return None;
+        }
+        let bucket = index / self.bucket_size;
+        let within_bucket = index % self.bucket_size;
+
+        let offset_pos = self.bucket_offsets_pos + bucket as usize * 8;
+        let mut pos = self.body_pos + self.int_at(offset_pos) as usize;
+
+        let (restart_len, consumed) = read_varint(&self.data[pos..]);
+        pos += consumed;
+        let mut term = self.data[pos..pos + restart_len as usize].to_vec();
+        pos += restart_len as usize;
+
+        for _ in 0..within_bucket {
+            let (shared_prefix_len, consumed) = read_varint(&self.data[pos..]);
+            pos += consumed;
+            let (suffix_len, consumed) = read_varint(&self.data[pos..]);
+            pos += consumed;
+            let suffix = &self.data[pos..pos + suffix_len as usize];
+            term.truncate(shared_prefix_len as usize);
+            term.extend_from_slice(suffix);
+            pos += suffix_len as usize;
+        }
+
+        Some(term)
+    }
+
+    fn int_at(&self, offset: usize) -> u64 {
+        u64::from_le_bytes(self.data[offset..offset + 8].try_into().unwrap())
+    }
+}
+
+/// Number of items per block in a [`CompressedPayloadVector`] built via its `FromIterator` impl.
+/// Override with [`CompressedPayloadVector::from_iter_with_options`].
+const DEFAULT_BLOCK_SIZE: u64 = 128;
+
+/// zstd compression level used by a [`CompressedPayloadVector`] built via its `FromIterator`
+/// impl. Override with [`CompressedPayloadVector::from_iter_with_options`].
+const DEFAULT_ZSTD_LEVEL: i32 = 3;
+
+/// Number of decompressed blocks a [`CompressedPayloadSlice`] keeps in its LRU cache.
+const DEFAULT_BLOCK_CACHE_SIZE: usize = 8;
+
+/// Size, in bytes, of the fixed header in front of the block-offset table: `num_items`,
+/// `block_size`, `compression_level`, and `num_blocks`, each a little-endian `u64`.
+const COMPRESSED_HEADER_SIZE: usize = 32;
+
+/// Block-compressed, memmap-friendly variant of [`PayloadVector`] for large lexicons and title
+/// lists that compress well but are still looked up by index (e.g. PISA term dictionaries).
+///
+/// Items are grouped into fixed-size blocks, and each block is zstd-compressed independently as
+/// a small [`PayloadVector`] of its own, so a random-access [`CompressedPayloadSlice::get`] only
+/// has to decompress the one block its index falls into (and repeated lookups into the same
+/// block hit an LRU cache of already-decompressed blocks instead of decompressing again).
+///
+/// On-disk layout: a header of four little-endian `u64`s (`num_items`, `block_size`,
+/// `compression_level`, `num_blocks`), a table of `num_blocks + 1` little-endian `u64` byte
+/// offsets into the compressed region, and then the compressed region itself: `num_blocks`
+/// zstd frames, each decompressing to a [`PayloadVector`]-encoded blob of up to `block_size`
+/// consecutive payloads.
+#[derive(Debug, Clone)]
+pub struct CompressedPayloadVector {
+    data: Vec<u8>,
+}
+
+impl CompressedPayloadVector {
+    /// Builds a compressed payload vector from `iter`, with `block_size` items per
+    /// independently-compressed block, compressed at the given zstd `level`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block_size` is zero.
+    #[must_use]
+    pub fn from_iter_with_options<Item, T>(iter: T, block_size: u64, level: i32) -> Self
+    where
+        Item: AsRef<[u8]>,
+        T: IntoIterator<Item = Item>,
+    {
+        assert!(block_size > 0, "block_size must be greater than zero");
+
+        fn flush_block(
+            block_items: &mut Vec<Vec<u8>>,
+            level: i32,
+            compressed_region: &mut Vec<u8>,
+            block_offsets: &mut Vec<u64>,
+        ) {
+            if block_items.is_empty() {
+                return;
+            }
+            let block_vector: PayloadVector = block_items.drain(..).collect();
+            let compressed = zstd::encode_all(block_vector.as_ref(), level)
+                .expect("compressing an in-memory buffer should not fail");
+            compressed_region.extend_from_slice(&compressed);
+            block_offsets.push(compressed_region.len() as u64);
+        }
+
+        let mut num_items: u64 = 0;
+        let mut block_offsets = vec![0_u64];
+        let mut compressed_region = Vec::<u8>::new();
+        let mut block_items = Vec::<Vec<u8>>::with_capacity(block_size as usize);
+
+        for item in iter {
+            block_items.push(item.as_ref().to_vec());
+            num_items += 1;
+            if block_items.len() as u64 == block_size {
+                flush_block(&mut block_items, level, &mut compressed_region, &mut block_offsets);
+            }
+        }
+        flush_block(&mut block_items, level, &mut compressed_region, &mut block_offsets);
+
+        let num_blocks = block_offsets.len() as u64 - 1;
+
+        let mut data = Vec::<u8>::new();
+        data.extend(num_items.to_le_bytes());
+        data.extend(block_size.to_le_bytes());
+        data.extend(i64::from(level).to_le_bytes());
+        data.extend(num_blocks.to_le_bytes());
+        for offset in &block_offsets {
+            data.extend(offset.to_le_bytes());
+        }
+        data.extend(compressed_region);
+
+        Self { data }
+    }
+
+    /// Borrows this vector as a [`CompressedPayloadSlice`].
+    #[must_use]
+    pub fn as_slice(&self) -> CompressedPayloadSlice<'_> {
+        CompressedPayloadSlice::new(&self.data)
+    }
+
+    /// Writes the underlying memory to the output.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if an error occurs while writing to the output.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.data)?;
+        writer.flush()
+    }
+}
+
+impl AsRef<[u8]> for CompressedPayloadVector {
+    fn as_ref(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl<Item> std::iter::FromIterator<Item> for CompressedPayloadVector
+where
+    Item: AsRef<[u8]>,
+{
+    fn from_iter<T: IntoIterator<Item = Item>>(iter: T) -> Self {
+        Self::from_iter_with_options(iter, DEFAULT_BLOCK_SIZE, DEFAULT_ZSTD_LEVEL)
+    }
+}
+
+/// Read-only, memmap-friendly view over bytes produced by [`CompressedPayloadVector`].
+///
+/// Unlike [`PayloadSlice::get`], [`CompressedPayloadSlice::get`] returns an owned `Vec<u8>`
+/// rather than a borrowed `&[u8]`: satisfying a lookup may require decompressing a block into
+/// this slice's LRU cache, so there is no borrowed buffer with a long enough lifetime to hand
+/// back a reference to.
+pub struct CompressedPayloadSlice<'a> {
+    data: &'a [u8],
+    num_items: u64,
+    block_size: u64,
+    block_offsets_pos: usize,
+    compressed_region_pos: usize,
+    cache: RefCell<LruCache<u64, PayloadVector>>,
+}
+
+impl<'a> CompressedPayloadSlice<'a> {
+    /// Wraps `data`, interpreting it as bytes written by [`CompressedPayloadVector::write`].
+    #[must_use]
+    pub fn new(data: &'a [u8]) -> Self {
+        let num_items = u64::from_le_bytes(data[0..8].try_into().unwrap());
+        let block_size = u64::from_le_bytes(data[8..16].try_into().unwrap());
+        let num_blocks = u64::from_le_bytes(data[24..32].try_into().unwrap());
+        let block_offsets_pos = COMPRESSED_HEADER_SIZE;
+        let compressed_region_pos = block_offsets_pos + (num_blocks as usize + 1) * 8;
+        Self {
+            data,
+            num_items,
+            block_size,
+            block_offsets_pos,
+            compressed_region_pos,
+            cache: RefCell::new(LruCache::new(
+                NonZeroUsize::new(DEFAULT_BLOCK_CACHE_SIZE).unwrap(),
+            )),
+        }
+    }
+
+    /// Returns the number of items.
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.num_items
+    }
+
+    /// Checks if the slice is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.num_items == 0
+    }
+
+    /// Returns the element at position `index`, or `None` if `index` is out of bounds.
+    /// Decompresses (and caches) the block `index` falls into, unless it is already cached.
+    #[must_use]
+    pub fn get(&self, index: u64) -> Option<Vec<u8>> {
+        if index >= self.num_items {
+            return None;
+        }
+        let block = index / self.block_size;
+        let within_block = index % self.block_size;
+
+        let mut cache = self.cache.borrow_mut();
+        if !cache.contains(&block) {
+            cache.put(block, self.decompress_block(block));
+        }
+        cache.get(&block)?.get(within_block).map(<[u8]>::to_vec)
+    }
+
+    fn decompress_block(&self, block: u64) -> PayloadVector {
+        let offset_pos = self.block_offsets_pos + block as usize * 8;
+        let start = self.compressed_region_pos + self.int_at(offset_pos) as usize;
+        let end = self.compressed_region_pos + self.int_at(offset_pos + 8) as usize;
+        let data = zstd::decode_all(&self.data[start..end])
+            .expect("a block written by CompressedPayloadVector should decompress cleanly");
+        PayloadVector { data }
+    }
+
+    fn int_at(&self, offset: usize) -> u64 {
+        u64::from_le_bytes(self.data[offset..offset + 8].try_into().unwrap())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -346,4 +994,160 @@ mod test {
         assert_payloads(&lex, &payloads);
         assert_payloads(PayloadSlice::new(lex.as_ref()), &payloads);
     }
+
+    #[test]
+    fn test_offset_width_auto_selection() {
+        let payloads = vec![
+            b"aardvark".as_ref(),
+            b"cat".as_ref(),
+            b"dog".as_ref(),
+            b"gnu".as_ref(),
+            b"mouse".as_ref(),
+            b"zebra".as_ref(),
+        ];
+        // Small enough to fit u16 offsets: FromIterator should pick the narrowest width.
+        let lex: PayloadVector = PayloadVector::with_auto_offset_width(payloads.clone());
+        assert_payloads(&lex, &payloads);
+        assert_payloads(PayloadSlice::new(lex.as_ref()), &payloads);
+
+        for width in [OffsetWidth::U16, OffsetWidth::U32, OffsetWidth::U64] {
+            let lex = PayloadVector::with_offset_width(payloads.clone(), width);
+            assert_payloads(&lex, &payloads);
+            assert_payloads(PayloadSlice::new(lex.as_ref()), &payloads);
+        }
+    }
+
+    #[test]
+    fn test_offset_width_legacy_layout_still_reads() {
+        // Mirrors the pre-tagged layout `PayloadVector` used to write: a `u64` length, then
+        // `len + 1` `u64` offsets, with no marker byte.
+        let mut data = Vec::<u8>::new();
+        data.extend(2_u64.to_le_bytes());
+        data.extend(0_u64.to_le_bytes());
+        data.extend(3_u64.to_le_bytes());
+        data.extend(6_u64.to_le_bytes());
+        data.extend_from_slice(b"dogcat");
+
+        let slice = PayloadSlice::new(&data);
+        assert_eq!(slice.len(), 2);
+        assert_eq!(slice.get(0), Some(b"dog".as_ref()));
+        assert_eq!(slice.get(1), Some(b"cat".as_ref()));
+    }
+
+    #[test]
+    #[should_panic(expected = "does not fit")]
+    fn test_offset_width_forced_too_narrow_panics() {
+        let payload = vec![0_u8; u16::MAX as usize + 1];
+        let _ = PayloadVector::with_offset_width(vec![payload], OffsetWidth::U16);
+    }
+
+    #[test]
+    fn test_position_and_contains() {
+        let payloads = vec![
+            b"aardvark".as_ref(),
+            b"cat".as_ref(),
+            b"dog".as_ref(),
+            b"gnu".as_ref(),
+            b"mouse".as_ref(),
+            b"zebra".as_ref(),
+        ];
+        let lex: PayloadVector = payloads.iter().map(|&b| b.to_vec()).collect();
+
+        for (idx, term) in payloads.iter().enumerate() {
+            assert_eq!(lex.position(term), Some(idx as u64));
+            assert!(lex.contains(term));
+        }
+        assert_eq!(lex.position(b"bee"), None);
+        assert!(!lex.contains(b"bee"));
+
+        assert_eq!(lex.binary_search_by(|e| e.cmp(b"bee")), Err(1));
+        assert_eq!(lex.binary_search_by(|e| e.cmp(b"aardvark")), Ok(0));
+        assert_eq!(lex.binary_search_by(|e| e.cmp(b"zed")), Err(6));
+    }
+
+    #[test]
+    fn test_binary_search_by_custom_comparator() {
+        // A comparator that case-folds both sides should find entries regardless of the key's
+        // case, even though the stored terms are lowercase.
+        let payloads = vec![b"ant".as_ref(), b"cat".as_ref(), b"dog".as_ref()];
+        let lex: PayloadVector = payloads.iter().map(|&b| b.to_vec()).collect();
+
+        let found = lex.binary_search_by(|element| {
+            element
+                .to_ascii_lowercase()
+                .cmp(&b"CAT".to_ascii_lowercase())
+        });
+        assert_eq!(found, Ok(1));
+    }
+
+    #[test]
+    fn test_compressed_element_access() {
+        let payloads = vec![
+            b"aardvark".as_ref(),
+            b"cat".as_ref(),
+            b"dog".as_ref(),
+            b"gnu".as_ref(),
+            b"mouse".as_ref(),
+            b"zebra".as_ref(),
+        ];
+        // A block size of 2 forces several blocks, exercising block boundaries.
+        let compressed = CompressedPayloadVector::from_iter_with_options(payloads.clone(), 2, 3);
+        let slice = compressed.as_slice();
+
+        assert_eq!(slice.len(), payloads.len() as u64);
+        for (idx, payload) in payloads.iter().enumerate() {
+            assert_eq!(slice.get(idx as u64).as_deref(), Some(*payload));
+        }
+        // Repeated, out-of-order lookups should still hit the right block.
+        assert_eq!(slice.get(5).as_deref(), Some(b"zebra".as_ref()));
+        assert_eq!(slice.get(0).as_deref(), Some(b"aardvark".as_ref()));
+        assert!(slice.get(6).is_none());
+    }
+
+    #[test]
+    fn test_compressed_roundtrip_through_bytes() -> io::Result<()> {
+        let payloads = vec!["dog", "cat", "gnu"];
+        let compressed: CompressedPayloadVector = payloads.into_iter().collect();
+
+        let mut bytes = Vec::<u8>::new();
+        compressed.write(&mut bytes)?;
+
+        let slice = CompressedPayloadSlice::new(&bytes);
+        assert_eq!(slice.get(0).as_deref(), Some(b"dog".as_ref()));
+        assert_eq!(slice.get(1).as_deref(), Some(b"cat".as_ref()));
+        assert_eq!(slice.get(2).as_deref(), Some(b"gnu".as_ref()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_front_coded_element_access() {
+        let terms = vec![
+            "aardvark", "ant", "anteater", "cat", "catalog", "dog", "gnu", "mouse", "zebra",
+        ];
+        // A bucket size of 3 forces multiple restarts, exercising both restart terms and
+        // replayed suffixes.
+        let lex = FrontCodedLexicon::from_sorted_iter(terms.clone(), 3);
+        let slice = lex.as_slice();
+
+        assert_eq!(slice.len(), terms.len() as u64);
+        for (idx, term) in terms.iter().enumerate() {
+            assert_eq!(slice.get(idx as u64).as_deref(), Some(term.as_bytes()));
+        }
+        assert!(slice.get(terms.len() as u64).is_none());
+    }
+
+    #[test]
+    fn test_front_coded_roundtrip_through_bytes() -> io::Result<()> {
+        let terms = vec!["dog", "doge", "dogs"];
+        let lex: FrontCodedLexicon = terms.into_iter().collect();
+
+        let mut bytes = Vec::<u8>::new();
+        lex.write(&mut bytes)?;
+
+        let slice = FrontCodedLexiconSlice::new(&bytes);
+        assert_eq!(slice.get(0).as_deref(), Some(b"dog".as_ref()));
+        assert_eq!(slice.get(1).as_deref(), Some(b"doge".as_ref()));
+        assert_eq!(slice.get(2).as_deref(), Some(b"dogs".as_ref()));
+        Ok(())
+    }
 }
@@ -32,6 +32,11 @@ struct Args {
     output: PathBuf,
     #[structopt(long, help = "Index description")]
     description: Option<String>,
+    #[structopt(
+        long,
+        help = "Read quantized BM25 impact scores from .scores instead of .freqs"
+    )]
+    quantize_impacts: bool,
 }
 
 fn main() {
@@ -42,6 +47,7 @@ fn main() {
         .terms_path(args.terms)
         .titles_path(args.documents)
         .output_path(args.output)
+        .quantize_impacts(args.quantize_impacts)
         .convert()
     {
         eprintln!("ERROR: {}", error);
@@ -0,0 +1,139 @@
+//! Recursive graph bisection (the "BP" algorithm) for computing a document ordering that
+//! groups documents sharing terms close together, shrinking the delta-gap encodings PISA
+//! builds on top of the uncompressed collection produced by [`crate::CiffToPisa`].
+//!
+//! The index is treated as a bipartite term-document graph. The current document id range is
+//! recursively split into two halves; within each split, documents are swapped across the
+//! boundary while doing so reduces the halves' expected log-gap cost, then each half is
+//! recursed into independently. See Dhulipala et al., "Compressing Graphs and Indexes with
+//! Recursive Graph Bisection" (KDD 2016).
+
+/// At or below this many documents, a partition is left unsplit.
+const LEAF_SIZE: usize = 16;
+
+/// Number of full gain-sorted swap passes attempted per partition before recursing.
+const SWAP_ITERATIONS: usize = 20;
+
+/// Computes a permutation of `0..num_docs` that groups documents sharing terms close
+/// together.
+///
+/// `postings` holds, for every term, the (current) document ids of its posting list.
+///
+/// Returns `order`, where `order[new_docid] == old_docid`.
+pub(crate) fn compute_order(postings: &[Vec<u32>], num_docs: usize) -> Vec<usize> {
+    let mut doc_terms: Vec<Vec<u32>> = vec![Vec::new(); num_docs];
+    for (term, docids) in postings.iter().enumerate() {
+        let term = term as u32;
+        for &docid in docids {
+            doc_terms[docid as usize].push(term);
+        }
+    }
+
+    let mut order: Vec<usize> = (0..num_docs).collect();
+    bisect(&mut order, &doc_terms, postings.len());
+    order
+}
+
+/// Recursively bisects `docs` (a slice of the permutation under construction, holding
+/// original document ids) in place.
+fn bisect(docs: &mut [usize], doc_terms: &[Vec<u32>], num_terms: usize) {
+    if docs.len() <= LEAF_SIZE {
+        return;
+    }
+
+    let mid = docs.len() / 2;
+    let left_n = mid as u32;
+    let right_n = (docs.len() - mid) as u32;
+
+    let mut left_degree = vec![0_u32; num_terms];
+    let mut right_degree = vec![0_u32; num_terms];
+    for &doc in &docs[..mid] {
+        for &term in &doc_terms[doc] {
+            left_degree[term as usize] += 1;
+        }
+    }
+    for &doc in &docs[mid..] {
+        for &term in &doc_terms[doc] {
+            right_degree[term as usize] += 1;
+        }
+    }
+
+    for _ in 0..SWAP_ITERATIONS {
+        let mut left_gains: Vec<(f64, usize)> = docs[..mid]
+            .iter()
+            .enumerate()
+            .map(|(i, &doc)| {
+                (
+                    move_gain(&doc_terms[doc], &left_degree, &right_degree, left_n, right_n),
+                    i,
+                )
+            })
+            .collect();
+        let mut right_gains: Vec<(f64, usize)> = docs[mid..]
+            .iter()
+            .enumerate()
+            .map(|(i, &doc)| {
+                (
+                    move_gain(&doc_terms[doc], &right_degree, &left_degree, right_n, left_n),
+                    i,
+                )
+            })
+            .collect();
+        left_gains.sort_by(|a, b| b.0.total_cmp(&a.0));
+        right_gains.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let mut swapped = false;
+        for (&(left_gain, li), &(right_gain, ri)) in left_gains.iter().zip(right_gains.iter()) {
+            if left_gain + right_gain <= 0.0 {
+                break;
+            }
+            let left_doc = docs[li];
+            let right_doc = docs[mid + ri];
+            for &term in &doc_terms[left_doc] {
+                left_degree[term as usize] -= 1;
+                right_degree[term as usize] += 1;
+            }
+            for &term in &doc_terms[right_doc] {
+                right_degree[term as usize] -= 1;
+                left_degree[term as usize] += 1;
+            }
+            docs.swap(li, mid + ri);
+            swapped = true;
+        }
+        if !swapped {
+            break;
+        }
+    }
+
+    let (left, right) = docs.split_at_mut(mid);
+    bisect(left, doc_terms, num_terms);
+    bisect(right, doc_terms, num_terms);
+}
+
+/// Approximates the total change in log-gap cost from moving a document through `terms`
+/// from a partition of size `from_n` to one of size `to_n`, given each term's current document
+/// count (degree) on both sides.
+fn move_gain(terms: &[u32], from_degree: &[u32], to_degree: &[u32], from_n: u32, to_n: u32) -> f64 {
+    terms
+        .iter()
+        .map(|&term| {
+            let term = term as usize;
+            let d_from = from_degree[term];
+            let d_to = to_degree[term];
+            let before = log_gap_cost(d_from, from_n) + log_gap_cost(d_to, to_n);
+            let after =
+                log_gap_cost(d_from.saturating_sub(1), from_n) + log_gap_cost(d_to + 1, to_n);
+            before - after
+        })
+        .sum()
+}
+
+/// Expected log-gap cost of encoding `deg` postings, assumed uniformly spread, among `n`
+/// documents: `deg * log2(n / (deg + 1))`.
+fn log_gap_cost(deg: u32, n: u32) -> f64 {
+    if deg == 0 {
+        0.0
+    } else {
+        f64::from(deg) * (f64::from(n) / f64::from(deg + 1)).log2()
+    }
+}
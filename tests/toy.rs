@@ -1,6 +1,9 @@
 #![allow(deprecated)]
 
-use ciff::{ciff_to_pisa, concat, pisa_to_ciff, CiffToPisa, PayloadSlice, PisaToCiff};
+use ciff::{
+    ciff_to_pisa, concat, pisa_to_ciff, Bm25Params, CiffReader, CiffToPisa, PayloadSlice,
+    PisaToCiff,
+};
 use std::fs::{read, read_to_string};
 use std::path::Path;
 use std::path::PathBuf;
@@ -85,6 +88,236 @@ fn test_toy_index() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Same toy index as `test_toy_index`, but converted with `.with_quantized_scores(4, ..)`: a
+/// parallel `.scores` file is produced holding BM25 impacts quantized into `[1, 15]` (4 bits),
+/// while `.freqs` keeps holding the raw, unquantized term frequencies.
+#[test]
+fn test_to_pisa_quantized_scores_custom_bits() -> anyhow::Result<()> {
+    let input_path = PathBuf::from("tests/test_data/toy-complete-20200309.ciff");
+    let temp = TempDir::new().unwrap();
+    let output_path = temp.path().join("coll");
+    CiffToPisa::default()
+        .input_path(input_path)
+        .output_paths(&output_path)
+        .with_quantized_scores(4, Bm25Params::default().k1, Bm25Params::default().b)
+        .convert()
+        .unwrap();
+
+    assert_eq!(
+        read(temp.path().join("coll.freqs"))?,
+        vec![
+            1, 0, 0, 0, 1, 0, 0, 0, // t0
+            1, 0, 0, 0, 1, 0, 0, 0, // t1
+            1, 0, 0, 0, 1, 0, 0, 0, // t2
+            1, 0, 0, 0, 1, 0, 0, 0, // t3
+            1, 0, 0, 0, 1, 0, 0, 0, // t4
+            3, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, // t5
+            2, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, // t6
+            3, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 3, 0, 0, 0, // t7
+            1, 0, 0, 0, 1, 0, 0, 0, // t8
+        ],
+        "quantized scores must not alter the raw term frequencies in .freqs"
+    );
+
+    let scores = read(temp.path().join("coll.scores"))?;
+    let as_u32s: Vec<u32> = scores
+        .chunks_exact(4)
+        .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+        .collect();
+    let dfs = [1, 1, 1, 1, 1, 3, 2, 3, 1]; // t0..t8, matching `test_toy_index`
+    let mut values = &as_u32s[..];
+    for df in dfs {
+        let (length, rest) = values.split_first().unwrap();
+        assert_eq!(*length, df);
+        let (impacts, rest) = rest.split_at(df as usize);
+        for &value in impacts {
+            assert!(
+                (1..=15).contains(&value),
+                "4-bit quantized impact {value} out of range"
+            );
+        }
+        values = rest;
+    }
+    assert!(values.is_empty());
+
+    Ok(())
+}
+
+/// Same toy index as `test_toy_index`, but converted with `.quantize_impacts(..)`: a parallel
+/// `.scores` file is produced holding BM25 impacts quantized into `[1, 255]`, while `.freqs`
+/// keeps holding the raw, unquantized term frequencies.
+#[test]
+fn test_to_pisa_quantized_impacts() -> anyhow::Result<()> {
+    let input_path = PathBuf::from("tests/test_data/toy-complete-20200309.ciff");
+    let temp = TempDir::new().unwrap();
+    let output_path = temp.path().join("coll");
+    CiffToPisa::default()
+        .input_path(input_path)
+        .output_paths(&output_path)
+        .quantize_impacts(Bm25Params::default())
+        .convert()
+        .unwrap();
+
+    let freqs = read(temp.path().join("coll.freqs"))?;
+    assert_eq!(
+        freqs,
+        vec![
+            1, 0, 0, 0, 1, 0, 0, 0, // t0
+            1, 0, 0, 0, 1, 0, 0, 0, // t1
+            1, 0, 0, 0, 1, 0, 0, 0, // t2
+            1, 0, 0, 0, 1, 0, 0, 0, // t3
+            1, 0, 0, 0, 1, 0, 0, 0, // t4
+            3, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, // t5
+            2, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, // t6
+            3, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 3, 0, 0, 0, // t7
+            1, 0, 0, 0, 1, 0, 0, 0, // t8
+        ],
+        "quantize_impacts must not alter the raw term frequencies in .freqs"
+    );
+
+    // `.scores` has the same posting-list layout as `.freqs` (one quantized impact per
+    // posting), only with different values, so the two files are the same length.
+    let scores = read(temp.path().join("coll.scores"))?;
+    assert_eq!(scores.len(), freqs.len());
+
+    // Every quantized impact (i.e. every sequence element, skipping each list's length
+    // prefix) is a u32 in `[1, 255]`.
+    let as_u32s: Vec<u32> = scores
+        .chunks_exact(4)
+        .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+        .collect();
+    let dfs = [1, 1, 1, 1, 1, 3, 2, 3, 1]; // t0..t8, matching `test_toy_index`
+    let mut values = &as_u32s[..];
+    for df in dfs {
+        let (length, rest) = values.split_first().unwrap();
+        assert_eq!(*length, df);
+        let (impacts, rest) = rest.split_at(df as usize);
+        for &value in impacts {
+            assert!(
+                (1..=255).contains(&value),
+                "quantized impact {value} out of range"
+            );
+        }
+        values = rest;
+    }
+    assert!(values.is_empty());
+
+    Ok(())
+}
+
+/// `PisaToCiff::quantize_impacts` reads the `.scores` file instead of `.freqs`, so the
+/// resulting CIFF's postings carry the same quantized impacts as `.scores`, not the raw term
+/// frequencies in `.freqs`.
+#[test]
+fn test_from_pisa_quantized_impacts() -> anyhow::Result<()> {
+    let input_path = PathBuf::from("tests/test_data/toy-complete-20200309.ciff");
+    let temp = TempDir::new().unwrap();
+    let pisa_path = temp.path().join("coll");
+    CiffToPisa::default()
+        .input_path(input_path)
+        .output_paths(&pisa_path)
+        .quantize_impacts(Bm25Params::default())
+        .convert()
+        .unwrap();
+
+    let ciff_output_path = temp.path().join("ciff");
+    PisaToCiff::default()
+        .index_paths(&pisa_path)
+        .terms_path(temp.path().join("coll.terms"))
+        .titles_path(temp.path().join("coll.documents"))
+        .output_path(&ciff_output_path)
+        .quantize_impacts(true)
+        .convert()?;
+
+    // Converting the resulting CIFF file back to PISA (without quantization) must reproduce
+    // the quantized impacts in its `.freqs`, since they were written into `Posting.tf`.
+    let pisa_copy = temp.path().join("copy");
+    CiffToPisa::default()
+        .input_path(&ciff_output_path)
+        .output_paths(&pisa_copy)
+        .convert()
+        .unwrap();
+
+    assert_eq!(
+        read(temp.path().join("coll.scores"))?,
+        read(temp.path().join("copy.freqs"))?
+    );
+
+    Ok(())
+}
+
+/// The toy index only has 3 documents, well under recursive graph bisection's leaf-size
+/// threshold, so `.recursive_graph_bisection(true)` leaves the document order untouched and
+/// must reproduce the plain conversion exactly.
+#[test]
+fn test_to_pisa_recursive_graph_bisection_below_leaf_size_is_noop() -> anyhow::Result<()> {
+    let input_path = PathBuf::from("tests/test_data/toy-complete-20200309.ciff");
+    let temp = TempDir::new().unwrap();
+
+    let plain_path = temp.path().join("plain");
+    CiffToPisa::default()
+        .input_path(&input_path)
+        .output_paths(&plain_path)
+        .convert()
+        .unwrap();
+
+    let bp_path = temp.path().join("bp");
+    CiffToPisa::default()
+        .input_path(&input_path)
+        .output_paths(&bp_path)
+        .recursive_graph_bisection(true)
+        .convert()
+        .unwrap();
+
+    for extension in ["docs", "freqs", "sizes", "documents"] {
+        assert_eq!(
+            read(temp.path().join(format!("plain.{extension}")))?,
+            read(temp.path().join(format!("bp.{extension}")))?,
+            "recursive_graph_bisection must not reorder a collection below the leaf-size \
+             threshold (.{extension})"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_ciff_reader() -> anyhow::Result<()> {
+    let input_path = PathBuf::from("tests/test_data/toy-complete-20200309.ciff");
+    let reader = CiffReader::open(&input_path)?;
+
+    assert_eq!(reader.num_documents(), 3);
+
+    let head = reader.term("head").expect("`head` is in the toy index");
+    assert_eq!(head.df(), 3);
+    assert_eq!(head.cf(), 3);
+    assert_eq!(head.postings(), &[(0, 1), (1, 1), (2, 1)]);
+
+    let text = reader.term("text").expect("`text` is in the toy index");
+    assert_eq!(text.df(), 3);
+    assert_eq!(text.cf(), 5);
+    assert_eq!(text.postings(), &[(0, 1), (1, 1), (2, 3)]);
+
+    let veri = reader.term("veri").expect("`veri` is in the toy index");
+    assert_eq!(veri.df(), 1);
+    assert_eq!(veri.cf(), 1);
+    assert_eq!(veri.postings(), &[(1, 1)]);
+
+    assert!(reader.term("missing").is_none());
+
+    assert_eq!(
+        reader.doc_record(0).unwrap().get_collection_docid(),
+        "WSJ_1"
+    );
+    assert_eq!(
+        reader.doc_record(2).unwrap().get_collection_docid(),
+        "DOC222"
+    );
+    assert!(reader.doc_record(3).is_none());
+
+    Ok(())
+}
+
 #[test]
 fn test_to_and_from_ciff() -> anyhow::Result<()> {
     let input_path = PathBuf::from("tests/test_data/toy-complete-20200309.ciff");